@@ -0,0 +1,110 @@
+// Slicing of strings by character (or grapheme cluster) index rather than byte index.
+
+use std::iter;
+
+/// Slice a string using char indices instead of byte indices.
+///
+/// The plain variants clamp out-of-range indices to the end of the string and return an empty
+/// slice when `start > end`; the `_checked` variants return `None` in those cases instead.
+///
+/// ```
+/// use common::CharSlice;
+///
+/// assert_eq!("hello".char_slice(1, 3), "el");
+/// assert_eq!("héllo".char_slice(1, 3), "él");
+/// assert_eq!("héllo".char_slice(3, 99), "lo");
+/// assert_eq!("héllo".char_slice(4, 2), "");
+/// assert_eq!("héllo".char_slice_checked(1, 5), Some("éllo"));
+/// assert_eq!("héllo".char_slice_checked(1, 6), None);
+/// assert_eq!("héllo".char_slice_checked(4, 2), None);
+/// ```
+pub trait CharSlice {
+    fn char_slice(&self, start: usize, end: usize) -> &str;
+    fn char_slice_checked(&self, start: usize, end: usize) -> Option<&str>;
+
+    /// Same as `char_slice`, but indices count extended grapheme clusters.
+    ///
+    /// ```
+    /// use common::CharSlice;
+    ///
+    /// let s = "ae\u{301}io";
+    /// assert_eq!(s.grapheme_slice(1, 2), "e\u{301}");
+    /// assert_eq!(s.grapheme_slice_checked(2, 5), None);
+    /// ```
+    #[cfg(feature = "graphemes")]
+    fn grapheme_slice(&self, start: usize, end: usize) -> &str;
+    #[cfg(feature = "graphemes")]
+    fn grapheme_slice_checked(&self, start: usize, end: usize) -> Option<&str>;
+}
+
+impl CharSlice for str {
+    fn char_slice(&self, start: usize, end: usize) -> &str {
+        let (start_byte, end_byte) = char_byte_bounds(self, start, end);
+        let end_byte = end_byte.unwrap_or(self.len());
+        &self[start_byte.unwrap_or(self.len()).min(end_byte)..end_byte]
+    }
+
+    fn char_slice_checked(&self, start: usize, end: usize) -> Option<&str> {
+        match char_byte_bounds(self, start, end) {
+            (Some(start_byte), Some(end_byte)) if start_byte <= end_byte => Some(&self[start_byte..end_byte]),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "graphemes")]
+    fn grapheme_slice(&self, start: usize, end: usize) -> &str {
+        let (start_byte, end_byte) = grapheme_byte_bounds(self, start, end);
+        let end_byte = end_byte.unwrap_or(self.len());
+        &self[start_byte.unwrap_or(self.len()).min(end_byte)..end_byte]
+    }
+
+    #[cfg(feature = "graphemes")]
+    fn grapheme_slice_checked(&self, start: usize, end: usize) -> Option<&str> {
+        match grapheme_byte_bounds(self, start, end) {
+            (Some(start_byte), Some(end_byte)) if start_byte <= end_byte => Some(&self[start_byte..end_byte]),
+            _ => None,
+        }
+    }
+}
+
+// Map char indices to byte offsets. When every byte up to the larger index is ASCII, char and byte indices
+// coincide, so the walk over the string can be skipped entirely.
+fn char_byte_bounds(s: &str, start: usize, end: usize) -> (Option<usize>, Option<usize>) {
+    let prefix = &s.as_bytes()[..start.max(end).min(s.len())];
+
+    if prefix.is_ascii() {
+        let to_byte = |i: usize| (i <= s.len()).then_some(i);
+        return (to_byte(start), to_byte(end));
+    }
+
+    byte_bounds(s.len(), s.char_indices().map(|(i, _)| i), start, end)
+}
+
+#[cfg(feature = "graphemes")]
+fn grapheme_byte_bounds(s: &str, start: usize, end: usize) -> (Option<usize>, Option<usize>) {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    byte_bounds(s.len(), s.grapheme_indices(true).map(|(i, _)| i), start, end)
+}
+
+// Single pass over the byte offsets at which each unit (char or grapheme) begins, returning the
+// offsets of the `start`th and `end`th unit. Index == unit count maps to the end of the string,
+// anything past that is `None`.
+fn byte_bounds(len: usize, offsets: impl Iterator<Item = usize>, start: usize, end: usize) -> (Option<usize>, Option<usize>) {
+    let mut start_byte = None;
+    let mut end_byte = None;
+
+    for (n, offset) in offsets.chain(iter::once(len)).enumerate() {
+        if n == start {
+            start_byte = Some(offset);
+        }
+        if n == end {
+            end_byte = Some(offset);
+        }
+        if n >= start.max(end) {
+            break;
+        }
+    }
+
+    (start_byte, end_byte)
+}
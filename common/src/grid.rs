@@ -0,0 +1,268 @@
+// Dense 2D grid stored as a single row-major Vec, addressed by (row, col).
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// Offsets (row, col) of the four orthogonal neighbors: up, left, right, down.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Offsets (row, col) of all eight surrounding neighbors, in reading order.
+pub const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    (0, -1),           (0, 1),
+    (1, -1),  (1, 0),  (1, 1),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GridError {
+    /// A row had a different length than the first row.
+    Ragged { row: usize, expected: usize, found: usize },
+    /// `from_vec` was given a cell count that doesn't match `width * height`.
+    SizeMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Ragged { row, expected, found } => {
+                write!(f, "row {} has {} cells, expected {}", row, found, expected)
+            }
+            GridError::SizeMismatch { expected, found } => {
+                write!(f, "grid needs {} cells, got {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where T: Clone, {
+        Self { width, height, cells: vec![fill; width * height] }
+    }
+
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Result<Self, GridError> {
+        if cells.len() != width * height {
+            return Err(GridError::SizeMismatch { expected: width * height, found: cells.len() });
+        }
+        Ok(Self { width, height, cells })
+    }
+
+    /// Build a grid from rows, which must all have the same length.
+    ///
+    /// ```
+    /// use common::{Grid, GridError};
+    ///
+    /// let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+    /// assert_eq!((grid.width(), grid.height()), (3, 2));
+    /// assert_eq!(grid[(1, 2)], 6);
+    ///
+    /// let ragged = Grid::from_rows(vec![vec![1, 2], vec![3]]);
+    /// assert_eq!(ragged, Err(GridError::Ragged { row: 1, expected: 2, found: 1 }));
+    /// ```
+    pub fn from_rows<I, R>(rows: I) -> Result<Self, GridError>
+    where I: IntoIterator<Item = R>, R: IntoIterator<Item = T>, {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+
+        for row in rows {
+            let before = cells.len();
+            cells.extend(row);
+            let found = cells.len() - before;
+
+            if height == 0 {
+                width = found;
+            } else if found != width {
+                return Err(GridError::Ragged { row: height, expected: width, found });
+            }
+            height += 1;
+        }
+
+        Ok(Self { width, height, cells })
+    }
+
+    /// Build a grid from rows of any length, padding short rows at the end with `fill`.
+    ///
+    /// ```
+    /// use common::Grid;
+    ///
+    /// let grid = Grid::from_rows_padded(vec![vec!['@'], vec!['@', '@', '@'], vec![]], '.');
+    /// assert_eq!(grid.to_string(), "@..\n@@@\n...\n");
+    /// ```
+    pub fn from_rows_padded<I, R>(rows: I, fill: T) -> Self
+    where I: IntoIterator<Item = R>, R: IntoIterator<Item = T>, T: Clone, {
+        let rows: Vec<Vec<T>> = rows.into_iter().map(|row| row.into_iter().collect()).collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+
+        let mut cells = Vec::with_capacity(width * height);
+        for mut row in rows {
+            row.resize(width, fill.clone());
+            cells.extend(row);
+        }
+
+        Self { width, height, cells }
+    }
+
+    /// Parse text into a grid, one row per line, converting each char with `f`.
+    pub fn parse_with<F>(text: &str, f: F) -> Result<Self, GridError>
+    where F: FnMut(char) -> T + Copy, {
+        Self::from_rows(text.lines().map(|line| line.chars().map(f)))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn in_bounds(&self, row: usize, col: usize) -> bool {
+        row < self.height && col < self.width
+    }
+
+    /// Flat index of (row, col), if it's inside the grid.
+    pub fn index_of(&self, row: usize, col: usize) -> Option<usize> {
+        self.in_bounds(row, col).then(|| row * self.width + col)
+    }
+
+    /// (row, col) of a flat index.
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        (index / self.width, index % self.width)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.index_of(row, col).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        self.index_of(row, col).map(|i| &mut self.cells[i])
+    }
+
+    /// Position reached by moving (row_offset, col_offset) from (row, col), if it's inside the grid.
+    pub fn offset(&self, row: usize, col: usize, row_offset: isize, col_offset: isize) -> Option<(usize, usize)> {
+        let r = row.checked_add_signed(row_offset)?;
+        let c = col.checked_add_signed(col_offset)?;
+        self.in_bounds(r, c).then_some((r, c))
+    }
+
+    pub fn get_offset(&self, row: usize, col: usize, row_offset: isize, col_offset: isize) -> Option<&T> {
+        self.offset(row, col, row_offset, col_offset).and_then(|(r, c)| self.get(r, c))
+    }
+
+    /// In-bounds neighbors of (row, col) at the given offsets, with their positions.
+    pub fn neighbors<'a>(&'a self, row: usize, col: usize, offsets: &'a [(isize, isize)]) -> impl Iterator<Item = ((usize, usize), &'a T)> + 'a {
+        offsets.iter()
+            .filter_map(move |&(dr, dc)| self.offset(row, col, dr, dc))
+            .map(|(r, c)| ((r, c), &self[(r, c)]))
+    }
+
+    /// The up-to-four orthogonal neighbors of (row, col).
+    pub fn neighbors4(&self, row: usize, col: usize) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.neighbors(row, col, &ORTHOGONAL)
+    }
+
+    /// The up-to-eight surrounding neighbors of (row, col).
+    ///
+    /// ```
+    /// use common::Grid;
+    ///
+    /// let grid: Grid<char> = "@.@\n.@.\n@@@".parse().unwrap();
+    /// assert_eq!(grid.neighbors8(1, 1).filter(|(_, c)| **c == '@').count(), 5);
+    /// assert_eq!(grid.neighbors8(0, 0).count(), 3);
+    /// ```
+    pub fn neighbors8(&self, row: usize, col: usize) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.neighbors(row, col, &ALL_DIRECTIONS)
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        (row < self.height).then(|| &self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(|r| &self.cells[r * self.width..(r + 1) * self.width])
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> + '_ {
+        let start = if col < self.width { col } else { self.cells.len() };
+        self.cells[start..].iter().step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(|c| self.column(c))
+    }
+
+    /// Every cell with its (row, col), in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.cells.iter().enumerate().map(|(i, cell)| (self.position(i), cell))
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where F: FnMut(&T) -> U, {
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(f).collect() }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(self.in_bounds(row, col), "({}, {}) out of bounds for {}x{} grid", row, col, self.width, self.height);
+        &self.cells[row * self.width + col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(self.in_bounds(row, col), "({}, {}) out of bounds for {}x{} grid", row, col, self.width, self.height);
+        &mut self.cells[row * self.width + col]
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, |c| c)
+    }
+}
+
+// One line per row, cells written back to back with no separator
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+// Sets of integers stored as sorted, disjoint inclusive ranges.
+
+use std::cmp::Ordering;
+
+/// Integer types an `IntervalSet` can hold.
+pub trait Integer: Copy + Ord {
+    fn checked_next(self) -> Option<Self>;
+    fn checked_prev(self) -> Option<Self>;
+    /// Number of values in `start..=end`, which can exceed every 64-bit type.
+    fn count(start: Self, end: Self) -> u128;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {
+            fn checked_next(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn checked_prev(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+
+            fn count(start: Self, end: Self) -> u128 {
+                (end as i128 - start as i128) as u128 + 1
+            }
+        }
+    )*};
+}
+
+impl_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// A set of integers kept as sorted, non-overlapping, non-adjacent inclusive ranges `(start, end)`.
+///
+/// Overlapping or touching ranges are merged as they're added, so the set always holds the fewest ranges
+/// covering its values. Ranges with `start > end` are empty and add nothing.
+///
+/// ```
+/// use common::IntervalSet;
+///
+/// let fresh: IntervalSet<u64> = [(3, 5), (10, 14), (16, 20), (12, 18)].into_iter().collect();
+/// assert_eq!(fresh.iter().collect::<Vec<_>>(), [(3, 5), (10, 20)]);
+/// assert!(fresh.contains(17) && !fresh.contains(8));
+/// assert_eq!(fresh.len(), 14);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self { ranges: Vec::new() }
+    }
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Build from ranges sorted by start, merging as it goes
+    fn from_sorted(sorted: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut ranges: Vec<(T, T)> = Vec::new();
+        for (start, end) in sorted {
+            if start > end {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if touches(*last, start) => last.1 = last.1.max(end),
+                _ => ranges.push((start, end)),
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Add every value in `start..=end`.
+    ///
+    /// ```
+    /// use common::IntervalSet;
+    ///
+    /// let mut set = IntervalSet::new();
+    /// set.insert(1, 3);
+    /// set.insert(7, 9);
+    /// set.insert(4, 6);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), [(1, 9)]);
+    /// ```
+    pub fn insert(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+
+        // ranges[first..last] overlap or touch the new one
+        let first = self.ranges.partition_point(|&range| !touches(range, start));
+        let last = match end.checked_next() {
+            Some(after) => self.ranges.partition_point(|&(s, _)| s <= after),
+            None => self.ranges.len(),
+        };
+
+        let merged = if first < last {
+            (start.min(self.ranges[first].0), end.max(self.ranges[last - 1].1))
+        } else {
+            (start, end)
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Whether `value` is in the set, by binary search over the ranges.
+    pub fn contains(&self, value: T) -> bool {
+        self.range_of(value).is_some()
+    }
+
+    /// The merged range holding `value`, if any.
+    pub fn range_of(&self, value: T) -> Option<(T, T)> {
+        let idx = self.ranges.partition_point(|&(_, end)| end < value);
+        self.ranges.get(idx).copied().filter(|&(start, _)| start <= value)
+    }
+
+    /// Number of values in the set, summed from the range bounds without walking the values; a u128 since
+    /// the full range of a 64-bit type holds one more value than the type can count.
+    ///
+    /// ```
+    /// use common::IntervalSet;
+    ///
+    /// assert_eq!(IntervalSet::<u64>::new().len(), 0);
+    ///
+    /// let mut all = IntervalSet::new();
+    /// all.insert(0, u64::MAX - 1);
+    /// all.insert(u64::MAX, u64::MAX);
+    /// assert_eq!(all.range_count(), 1);
+    /// assert_eq!(all.len(), 1 << 64);
+    ///
+    /// let signed: IntervalSet<i64> = [(i64::MIN, -1), (1, i64::MAX)].into_iter().collect();
+    /// assert_eq!(signed.len(), u64::MAX as u128);
+    /// ```
+    pub fn len(&self) -> u128 {
+        self.ranges.iter().map(|&(start, end)| T::count(start, end)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of (merged) ranges.
+    pub fn range_count(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// The merged ranges in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = (T, T)> + '_ {
+        self.ranges.iter().copied()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        // merge the two sorted lists by start, then coalesce
+        let mut ranges = Vec::with_capacity(self.ranges.len() + other.ranges.len());
+        let (mut i, mut j) = (0, 0);
+        loop {
+            let next = match (self.ranges.get(i), other.ranges.get(j)) {
+                (Some(&a), Some(&b)) if b < a => {
+                    j += 1;
+                    b
+                }
+                (Some(&a), _) => {
+                    i += 1;
+                    a
+                }
+                (None, Some(&b)) => {
+                    j += 1;
+                    b
+                }
+                (None, None) => break,
+            };
+            ranges.push(next);
+        }
+        Self::from_sorted(ranges)
+    }
+
+    /// ```
+    /// use common::IntervalSet;
+    ///
+    /// let a: IntervalSet<i32> = [(0, 10), (20, 30)].into_iter().collect();
+    /// let b: IntervalSet<i32> = [(5, 25)].into_iter().collect();
+    /// assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [(5, 10), (20, 25)]);
+    /// assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), [(0, 4), (26, 30)]);
+    /// assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), [(0, 30)]);
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(&a), Some(&b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let (start, end) = (a.0.max(b.0), a.1.min(b.1));
+            if start <= end {
+                ranges.push((start, end));
+            }
+            // whichever range ends first can't overlap anything further
+            match a.1.cmp(&b.1) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Values in `self` but not in `other`.
+    ///
+    /// ```
+    /// use common::IntervalSet;
+    ///
+    /// let all: IntervalSet<u8> = [(0, 255)].into_iter().collect();
+    /// let ends: IntervalSet<u8> = [(0, 0), (255, 255)].into_iter().collect();
+    /// assert_eq!(all.difference(&ends).iter().collect::<Vec<_>>(), [(1, 254)]);
+    /// assert!(all.difference(&all).is_empty());
+    /// ```
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for &(start, end) in &self.ranges {
+            // skip ranges of `other` that end before this one starts
+            while other.ranges.get(j).is_some_and(|&(_, e)| e < start) {
+                j += 1;
+            }
+
+            let mut rest = Some(start);
+            let mut k = j;
+            while let (Some(from), Some(&(cut_start, cut_end))) = (rest, other.ranges.get(k)) {
+                if cut_start > end {
+                    break;
+                }
+                if let Some(before) = cut_start.checked_prev() && from <= before {
+                    ranges.push((from, before));
+                }
+                rest = cut_end.checked_next().filter(|&after| after <= end);
+                k += 1;
+            }
+            if let Some(from) = rest {
+                ranges.push((from, end));
+            }
+        }
+        Self { ranges }
+    }
+}
+
+// Whether a range starting at `start` overlaps or directly follows `range`; only valid if `range` doesn't start after `start`
+fn touches<T: Integer>((_, end): (T, T), start: T) -> bool {
+    end.checked_next().is_none_or(|after| after >= start)
+}
+
+impl<T: Integer> FromIterator<(T, T)> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(iter: I) -> Self {
+        let mut ranges: Vec<(T, T)> = iter.into_iter().collect();
+        ranges.sort_unstable();
+        Self::from_sorted(ranges)
+    }
+}
+
+impl<'a, T: Integer> IntoIterator for &'a IntervalSet<T> {
+    type Item = (T, T);
+    type IntoIter = std::iter::Copied<std::slice::Iter<'a, (T, T)>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.iter().copied()
+    }
+}
+
+impl<T: Integer> Extend<(T, T)> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = (T, T)>>(&mut self, iter: I) {
+        *self = self.union(&iter.into_iter().collect());
+    }
+}
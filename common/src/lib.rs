@@ -0,0 +1,9 @@
+// Shared helpers used across the daily puzzle crates.
+
+pub mod char_slice;
+pub mod grid;
+pub mod interval_set;
+
+pub use char_slice::CharSlice;
+pub use grid::{Grid, GridError};
+pub use interval_set::IntervalSet;
@@ -1,7 +1,10 @@
 
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, IsTerminal};
 use std::path::Path;
+use std::process;
+use common::CharSlice;
 
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where P: AsRef<Path>, {
@@ -9,37 +12,63 @@ where P: AsRef<Path>, {
     Ok(io::BufReader::new(file).lines())
 }
 
-pub trait CharSlice {
-    fn char_slice(&self, start: usize, end: usize) -> &str;
+// Joltage picked from a bank, along with the (char) indices of the batteries that produced it
+struct Joltage {
+    value: u64,
+    indices: Vec<usize>,
 }
 
-impl CharSlice for str {
-    fn char_slice(&self, start: usize, end: usize) -> &str {
-        let start_byte = self.char_indices().nth(start).map(|(i, _)| i).unwrap_or(self.len());
-        let end_byte = self.char_indices().nth(end).map(|(i, _)| i).unwrap_or(self.len());
-        &self[start_byte..end_byte]
-    }
-}
+const DAY1_DIGITS: usize = 2;
+const DAY2_DIGITS: usize = 12;
 
-fn get_joltage_day1(bank: &str) -> u64 {
-    get_joltage_num_digits(bank, 2)
+fn get_joltage_day1(bank: &str) -> Joltage {
+    get_joltage_num_digits(bank, DAY1_DIGITS)
 }
 
-fn get_joltage_day2(bank: &str) -> u64 {
-    get_joltage_num_digits(bank, 12)
+fn get_joltage_day2(bank: &str) -> Joltage {
+    get_joltage_num_digits(bank, DAY2_DIGITS)
 }
 
-fn get_joltage_num_digits(bank: &str, num_digits: usize) -> u64 {
+fn get_joltage_num_digits(bank: &str, num_digits: usize) -> Joltage {
     let mut joltage_vec: Vec<char> = [].to_vec();
+    let mut indices: Vec<usize> = Vec::with_capacity(num_digits);
     let mut next_start_index: usize = 0;
 
     for digit in (0..=(num_digits.saturating_sub(1))).rev() {
-        let (pos, val) = get_joltage_single_high(&bank.char_slice(next_start_index, bank.chars().count().saturating_sub(digit)));
+        let (pos, val) = get_joltage_single_high(bank.char_slice(next_start_index, bank.chars().count().saturating_sub(digit)));
+        indices.push(next_start_index + pos);
         next_start_index += pos.saturating_add(1); //each call moves start index to 0 since it's a sub-slice so add this to previous starting location
         joltage_vec.push(val);
     }
 
-    joltage_vec.iter().collect::<String>().parse::<u64>().unwrap_or(0)
+    Joltage {
+        value: joltage_vec.iter().collect::<String>().parse::<u64>().unwrap_or(0),
+        indices,
+    }
+}
+
+// Render the bank with the selected batteries highlighted: bold green when writing to a terminal, wrapped in [] otherwise
+fn render_selection(bank: &str, indices: &[usize], color: bool) -> String {
+    let mut out = String::with_capacity(bank.len() * 2);
+    let mut selected = indices.iter().peekable();
+
+    for (i, c) in bank.chars().enumerate() {
+        if selected.next_if_eq(&&i).is_some() {
+            if color {
+                out.push_str("\x1b[1;32m");
+                out.push(c);
+                out.push_str("\x1b[0m");
+            } else {
+                out.push('[');
+                out.push(c);
+                out.push(']');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
 }
 
 fn get_joltage_single_high(bank: &str) -> (usize, char) {
@@ -50,7 +79,8 @@ fn get_joltage_single_high(bank: &str) -> (usize, char) {
                 return None;
             }
 
-            if c.is_ascii_digit() && c > best.1 {
+            // banks are validated before solving, so every char here is an ASCII digit
+            if c > best.1 {
                 *best = (i, c);
             }
 
@@ -60,18 +90,167 @@ fn get_joltage_single_high(bank: &str) -> (usize, char) {
         .unwrap_or((usize::MIN, '0'))
 }
 
+// What to do with a bank containing something other than ASCII digits
+#[derive(Clone, Copy, PartialEq)]
+enum InvalidPolicy {
+    Error,  // stop at the first offending bank
+    Skip,   // leave the bank out of the totals
+    Strip,  // drop the offending chars and solve what's left
+}
+
+impl InvalidPolicy {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "strip" => Ok(Self::Strip),
+            other => Err(format!("Unknown invalid-char policy '{}' (expected error, skip or strip)", other)),
+        }
+    }
+}
+
+// Problem found with a single bank, tagged with its (1-based) input line
+enum BankIssue {
+    InvalidChars { line: usize, chars: Vec<(usize, char)> },
+    TooShort { line: usize, digits: usize, part: usize },  // too few digits for that part (and any later one)
+}
+
+impl fmt::Display for BankIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankIssue::InvalidChars { line, chars } => {
+                write!(f, "line {}: invalid chars", line)?;
+                for (col, c) in chars {
+                    write!(f, " {:?}@{}", c, col + 1)?;
+                }
+                Ok(())
+            }
+            BankIssue::TooShort { line, digits, part: 1 } => {
+                write!(f, "line {}: only {} digits, need at least {}", line, digits, DAY1_DIGITS)
+            }
+            BankIssue::TooShort { line, digits, .. } => {
+                write!(f, "line {}: only {} digits, need at least {} for part 2 (counted in part 1 only)", line, digits, DAY2_DIGITS)
+            }
+        }
+    }
+}
+
+// Both joltage picks for a single bank, kept together with the bank so results can be reported in input order
+struct BankResult {
+    bank: String,
+    day1: Joltage,
+    day2: Option<Joltage>,  // None when the bank is too short for part 2
+    issues: Vec<BankIssue>,  // chars stripped or a part 2 shortfall, neither of which stops the bank being solved
+}
+
+fn process_bank(line: usize, bank: String, policy: InvalidPolicy) -> Result<BankResult, BankIssue> {
+    let invalid: Vec<(usize, char)> = bank.chars().enumerate().filter(|(_, c)| !c.is_ascii_digit()).collect();
+
+    let (bank, mut issues) = if invalid.is_empty() {
+        (bank, Vec::new())
+    } else if policy == InvalidPolicy::Strip {
+        (bank.chars().filter(char::is_ascii_digit).collect(), vec![BankIssue::InvalidChars { line, chars: invalid }])
+    } else {
+        return Err(BankIssue::InvalidChars { line, chars: invalid });
+    };
+
+    // bank is all ASCII digits at this point, so its byte length is its digit count; each part is checked
+    // against its own digit count, so a bank too short for part 2 still counts towards part 1
+    if bank.len() < DAY1_DIGITS {
+        return Err(BankIssue::TooShort { line, digits: bank.len(), part: 1 });
+    }
+
+    let day1 = get_joltage_day1(&bank);
+    let day2 = if bank.len() < DAY2_DIGITS {
+        issues.push(BankIssue::TooShort { line, digits: bank.len(), part: 2 });
+        None
+    } else {
+        Some(get_joltage_day2(&bank))
+    };
+    Ok(BankResult { bank, day1, day2, issues })
+}
+
+fn report_bank(result: &BankResult, color: bool) {
+    println!("Bank (part 1): {}", render_selection(&result.bank, &result.day1.indices, color));
+    match &result.day2 {
+        Some(day2) => {
+            println!("Bank (part 2): {}", render_selection(&result.bank, &day2.indices, color));
+            println!("Jolts (part 1, part 2): {}, {}", result.day1.value, day2.value);
+        }
+        None => {
+            println!("Bank (part 2): too short, need at least {} digits", DAY2_DIGITS);
+            println!("Jolts (part 1, part 2): {}, -", result.day1.value);
+        }
+    }
+}
+
+// Number of banks read into memory and solved concurrently before their results are printed
+#[cfg(feature = "parallel")]
+const BATCH_SIZE: usize = 1 << 16;
+
 fn main() {
     let mut total_joltage_day1: u64 = 0;
     let mut total_joltage_day2: u64 = 0;
+    let mut issues: Vec<BankIssue> = Vec::new();
+    let color = io::stdout().is_terminal();
+
+    // only option is the invalid char policy: --invalid <error|skip|strip>, defaulting to error
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let policy = match args.as_slice() {
+        [] => Ok(InvalidPolicy::Error),
+        [flag, value] if flag == "--invalid" => InvalidPolicy::parse(value),
+        _ => Err(String::from("Usage: day03 [--invalid <error|skip|strip>]")),
+    }.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+
+    let mut handle = |outcome: Result<BankResult, BankIssue>| match outcome {
+        Ok(mut result) => {
+            total_joltage_day1 += result.day1.value;
+            total_joltage_day2 += result.day2.as_ref().map_or(0, |day2| day2.value);
+            report_bank(&result, color);
+            issues.append(&mut result.issues);
+        }
+        Err(issue) if policy == InvalidPolicy::Error => {
+            eprintln!("Invalid bank on {}", issue);
+            process::exit(1);
+        }
+        Err(issue) => issues.push(issue),
+    };
 
     if let Ok(lines) = read_lines("./input") {
-        for bank in lines.map_while(Result::ok) {            
-            print!("Bank: {}", bank);
-            let bank_joltage_day1 = get_joltage_day1(&bank);
-            let bank_joltage_day2 = get_joltage_day2(&bank);
-            total_joltage_day1 += bank_joltage_day1;
-            total_joltage_day2 += bank_joltage_day2;
-            println!(" Jolts (part 1, part 2): {}, {}",bank_joltage_day1, bank_joltage_day2);
+        let mut banks = lines.map_while(Result::ok).enumerate().map(|(i, bank)| (i + 1, bank));
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            // banks are independent, so solve a batch at a time in parallel; collect() keeps input order
+            loop {
+                let batch: Vec<(usize, String)> = banks.by_ref().take(BATCH_SIZE).collect();
+                if batch.is_empty() {
+                    break;
+                }
+
+                let outcomes: Vec<_> = batch.into_par_iter().map(|(line, bank)| process_bank(line, bank, policy)).collect();
+                outcomes.into_iter().for_each(&mut handle);
+            }
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        for (line, bank) in banks.by_ref() {
+            handle(process_bank(line, bank, policy));
+        }
+    }
+
+    if !issues.is_empty() {
+        let action = if policy == InvalidPolicy::Strip { "stripped or skipped" } else { "skipped" };
+        // banks only too short for part 2 were still solved for part 1, which their line says
+        let noted = if issues.iter().any(|issue| matches!(issue, BankIssue::TooShort { part: 2, .. })) { " unless noted" } else { "" };
+        println!("Offending banks ({}{}):", action, noted);
+        for issue in &issues {
+            println!("  {}", issue);
         }
     }
     println!("Total Joltage (part 1, part 2): {}, {}", total_joltage_day1, total_joltage_day2);
@@ -0,0 +1,123 @@
+use std::cmp::Reverse;
+
+use common::Grid;
+
+use crate::matrix::{RollMatrix, Simulation};
+
+/// The rolls no wave ever removes, drawn as `@` on an otherwise empty grid.
+pub fn core(sim: &Simulation) -> Grid<char> {
+    let mut grid = Grid::new(sim.width, sim.height, '.');
+    for pos in sim.survivors() {
+        grid[pos] = '@';
+    }
+    grid
+}
+
+fn check_roll(rm: &RollMatrix, (row, col): (usize, usize)) -> Result<(), String> {
+    let rolls = rm.rolls();
+    match rolls.roll_at((row, col)) {
+        _ if row >= rolls.height() || col >= rolls.width() => Err(format!("({}, {}) is outside the {}x{} grid", row, col, rolls.width(), rolls.height())),
+        Some(_) => Ok(()),
+        None => Err(format!("There is no roll at ({}, {})", row, col)),
+    }
+}
+
+// Whether the roll at `pos` of `rm` is still standing at the end of `sim`
+fn survives(rm: &RollMatrix, sim: &Simulation, pos: (usize, usize)) -> bool {
+    rm.rolls().roll_at(pos).is_some_and(|id| sim.removed_at[id].is_none())
+}
+
+// Most empty cells `protect` fills at once, and so the most simulations spent shrinking a set that works
+const MAX_CANDIDATES: usize = 1000;
+
+/// Empty cells that, once filled with rolls, keep the roll at `pos` from ever being removed; empty if it
+/// already survives, `None` if filling every empty cell of the grid doesn't help (e.g. a corner roll that can't
+/// reach enough neighbors, or a rule where more neighbors make a roll easier to remove).
+///
+/// Every empty cell within a square around the roll is filled, the square growing a cell at a time until the
+/// roll survives, since a cluster can need support from well outside the roll's own neighborhood. The cells
+/// are then dropped again one by one, farthest first, as long as the roll still survives without them. So none
+/// of the cells returned can be left out, but a smaller set elsewhere may exist. Gives up with an error once
+/// the square holds more than `MAX_CANDIDATES` empty cells.
+pub fn protect(rm: &RollMatrix, pos: (usize, usize)) -> Result<Option<Vec<(usize, usize)>>, String> {
+    check_roll(rm, pos)?;
+    if survives(rm, &rm.simulate(None), pos) {
+        return Ok(Some(Vec::new()));
+    }
+    if rm.always_accessible(pos) {
+        return Ok(None);
+    }
+
+    let (width, height) = (rm.rolls().width(), rm.rolls().height());
+    let reach = pos.0.max(height - 1 - pos.0).max(pos.1).max(width - 1 - pos.1);
+    let mut tried = 0;
+
+    for radius in 1..=reach {
+        let empty: Vec<(usize, usize)> = (pos.0.saturating_sub(radius)..=(pos.0 + radius).min(height - 1))
+            .flat_map(|row| (pos.1.saturating_sub(radius)..=(pos.1 + radius).min(width - 1)).map(move |col| (row, col)))
+            .filter(|&cell| rm.rolls().roll_at(cell).is_none())
+            .collect();
+
+        // a ring without empty cells changes nothing
+        if empty.len() == tried {
+            continue;
+        }
+        if empty.len() > MAX_CANDIDATES {
+            return Err(format!(
+                "Search limit reached: no rolls found that keep ({}, {}) from being removed before the search grew past {} empty cells",
+                pos.0, pos.1, MAX_CANDIDATES,
+            ));
+        }
+        tried = empty.len();
+
+        let matrix = rm.with_cells(&empty, true);
+        if survives(&matrix, &matrix.simulate(None), pos) {
+            return Ok(Some(shrink(rm, pos, empty)));
+        }
+    }
+    Ok(None)
+}
+
+// Drop every cell of `added` the roll at `pos` can survive without, farthest first
+fn shrink(rm: &RollMatrix, pos: (usize, usize), mut added: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    added.sort_by_key(|&(row, col)| Reverse(row.abs_diff(pos.0).max(col.abs_diff(pos.1))));
+
+    let mut i = 0;
+    while i < added.len() {
+        let without: Vec<(usize, usize)> = added.iter().enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, &cell)| cell)
+            .collect();
+        let matrix = rm.with_cells(&without, true);
+        if survives(&matrix, &matrix.simulate(None), pos) {
+            added = without;
+        } else {
+            i += 1;
+        }
+    }
+
+    added.sort_unstable();
+    added
+}
+
+/// What happens when one roll is taken away before the first wave.
+pub struct Cascade {
+    /// Rolls removed in total, counting the chosen one.
+    pub removed: u64,
+    /// Rolls removed without intervention.
+    pub baseline: u64,
+    /// Rolls of the stable core that now fall as well.
+    pub core_lost: usize,
+}
+
+pub fn remove_first(rm: &RollMatrix, pos: (usize, usize), baseline: &Simulation) -> Result<Cascade, String> {
+    check_roll(rm, pos)?;
+
+    let matrix = rm.with_cells(&[pos], false);
+    let sim = matrix.simulate(None);
+    let core_lost = baseline.survivors()
+        .filter(|&survivor| survivor != pos && !survives(&matrix, &sim, survivor))
+        .count();
+
+    Ok(Cascade {removed: sim.total_removed() + 1, baseline: baseline.total_removed(), core_lost})
+}
@@ -0,0 +1,291 @@
+use std::error::Error;
+use std::num::NonZeroU16;
+use std::path::{Path, PathBuf};
+
+use image::{ImageBuffer, Rgb};
+use common::Grid;
+
+use crate::palette::Palette;
+use crate::render::{Cell, FADE_STEPS};
+
+/// Where and how to write the animation; each output is only written when its path is given.
+pub struct AnimationOptions {
+    pub gif: Option<PathBuf>,
+    pub apng: Option<PathBuf>,
+    /// Directory to write every frame into as a numbered PNG.
+    pub png_frames: Option<PathBuf>,
+    pub asciicast: Option<PathBuf>,
+    /// Final frame only, as a PNG.
+    pub png: Option<PathBuf>,
+    /// Box the image is fitted into; explicit `width`/`height` override either side.
+    pub target_size: u32,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Fixed pixels per cell (may be fractional), instead of fitting the box.
+    pub scale: Option<f64>,
+    /// Lower bound on pixels per cell, so huge grids still stay legible (at the cost of a larger image).
+    pub min_scale: f64,
+    /// Separate cells with 1px lines when they're at least 3px wide.
+    pub gridlines: bool,
+    /// Only wave numbers divisible by this get a frame (the first and last frame are always kept).
+    pub every: usize,
+    pub delay_ms: u16,
+    pub hold_start_ms: u16,
+    pub hold_end_ms: u16,
+    /// Number of times to play the animation, `None` to loop forever.
+    pub plays: Option<NonZeroU16>,
+    pub palette: Palette,
+    /// Draw a strip of every cell state's color below the grid.
+    pub legend: bool,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            gif: None,
+            apng: None,
+            png_frames: None,
+            asciicast: None,
+            png: None,
+            target_size: 1000,
+            width: None,
+            height: None,
+            scale: None,
+            min_scale: 0.0,
+            gridlines: false,
+            every: 1,
+            delay_ms: 75,
+            hold_start_ms: 2000,
+            hold_end_ms: 2000,
+            plays: None,
+            palette: Palette::heat(),
+            legend: false,
+        }
+    }
+}
+
+impl AnimationOptions {
+    /// Times the animation repeats after its first play, as GIF counts them; `None` to loop forever.
+    pub fn repeats(&self) -> Option<u16> {
+        self.plays.map(|plays| plays.get() - 1)
+    }
+
+    /// Times the animation plays as APNG counts them, 0 meaning forever.
+    pub fn num_plays(&self) -> u32 {
+        self.plays.map_or(0, |plays| plays.get().into())
+    }
+}
+
+// Every pixel is drawn with one of a fixed set of inks: the cell states in legend order, then the gridlines and
+// the legend background. Frames are rasterized to ink indices, which double as the GIF's global palette indices;
+// the GIF palette has one extra, transparent entry for pixels that didn't change since the previous frame.
+const GRIDLINE: Rgb<u8> = Rgb([40, 40, 40]);
+const LEGEND_GAP: Rgb<u8> = Rgb([64, 64, 64]);
+const GRIDLINE_INK: u8 = FADE_STEPS as u8 + 2;
+const LEGEND_GAP_INK: u8 = FADE_STEPS as u8 + 3;
+pub const UNCHANGED_INK: u8 = FADE_STEPS as u8 + 4;
+
+/// Most pixels a frame may have (256 megapixels): a byte each as a raster, three once converted to an image.
+pub const MAX_PIXELS: u64 = 1 << 28;
+
+// GIF stores its dimensions as 16-bit numbers
+const GIF_MAX_SIDE: u64 = u16::MAX as u64;
+
+fn ink(cell: Cell) -> u8 {
+    match cell {
+        Cell::Roll => 0,
+        Cell::Fading(n) => 1 + n as u8,
+        Cell::Empty => FADE_STEPS as u8 + 1,
+    }
+}
+
+pub fn inks(palette: &Palette) -> Vec<Rgb<u8>> {
+    let mut inks: Vec<Rgb<u8>> = palette.legend().into_iter().map(Rgb).collect();
+    inks.extend([GRIDLINE, LEGEND_GAP]);
+    inks
+}
+
+/// A rendered frame as row-major ink indices.
+#[derive(Clone)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    fn filled(width: u32, height: u32, ink: u8) -> Self {
+        Self {width, height, pixels: vec![ink; width as usize * height as usize]}
+    }
+
+    pub fn to_image(&self, palette: &Palette) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let inks = inks(palette);
+        ImageBuffer::from_fn(self.width, self.height, |x, y| inks[self.pixels[self.index(x, y)] as usize])
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Smallest (left, top, width, height) rectangle containing every pixel that differs from `previous`.
+    pub fn changed_rect(&self, previous: &Raster) -> Option<(u32, u32, u32, u32)> {
+        let mut rect: Option<(u32, u32, u32, u32)> = None;
+
+        for y in 0..self.height {
+            let start = self.index(0, y);
+            let row = &self.pixels[start..start + self.width as usize];
+            let prev = &previous.pixels[start..start + self.width as usize];
+
+            let Some(first) = row.iter().zip(prev).position(|(a, b)| a != b) else { continue };
+            let last = row.iter().zip(prev).rposition(|(a, b)| a != b).unwrap_or(first);
+            let (first, last) = (first as u32, last as u32);
+
+            rect = Some(match rect {
+                None => (first, y, last, y),
+                Some((x0, y0, x1, _)) => (x0.min(first), y0, x1.max(last), y),
+            });
+        }
+
+        rect.map(|(x0, y0, x1, y1)| (x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+    }
+
+    /// Pixels inside a rectangle, with those equal to `previous` replaced by `UNCHANGED_INK`.
+    pub fn crop(&self, (left, top, width, height): (u32, u32, u32, u32), previous: Option<&Raster>) -> Vec<u8> {
+        (top..top + height)
+            .flat_map(|y| {
+                let start = self.index(left, y);
+                let range = start..start + width as usize;
+                let prev = previous.map(|prev| &prev.pixels[range.clone()]);
+
+                self.pixels[range].iter().enumerate().map(move |(i, &ink)| match prev {
+                    Some(prev) if prev[i] == ink => UNCHANGED_INK,
+                    _ => ink,
+                })
+            })
+            .collect()
+    }
+}
+
+// Pixels per cell: the fixed scale if given, else the largest scale that fits the box. Fitting keeps whole
+// pixels per cell whenever possible so cells stay evenly sized, and only goes fractional for grids larger than the box.
+fn scale_for(width: u32, height: u32, options: &AnimationOptions) -> f64 {
+    let scale = options.scale.unwrap_or_else(|| {
+        let box_w = options.width.unwrap_or(options.target_size) as f64;
+        let box_h = options.height.unwrap_or(options.target_size) as f64;
+        let fit = (box_w / width.max(1) as f64).min(box_h / height.max(1) as f64);
+        if fit >= 1.0 { fit.floor() } else { fit }
+    });
+    scale.max(options.min_scale)
+}
+
+/// Image dimensions for a width x height grid; never zero, even for an empty grid. Kept as floats so an
+/// oversized request can be rejected by `check_size` before anything is converted or allocated.
+fn image_size(width: u32, height: u32, options: &AnimationOptions) -> (f64, f64) {
+    let scale = scale_for(width, height, options);
+    let side = |cells: u32| (cells as f64 * scale).round().max(1.0);
+    (side(width), side(height))
+}
+
+// Height of the legend strip drawn below an image `height` pixels tall
+fn legend_height(height: u32) -> u32 {
+    (height / 20).max(8)
+}
+
+/// Make sure frames of `grid` can be rendered with these options: at most `MAX_PIXELS` pixels (legend
+/// included), and no side over 65535 pixels when a GIF is written. Checked before the first frame is
+/// rasterized, so a huge `--scale` fails with an error instead of running out of memory.
+pub fn check_size(grid: &Grid<Cell>, options: &AnimationOptions) -> Result<(), String> {
+    let (width, mut height) = image_size(grid.width() as u32, grid.height() as u32, options);
+    if options.legend {
+        height += legend_height(height.min(u32::MAX as f64) as u32) as f64;
+    }
+
+    let too_large = |limit: String| {
+        Err(format!("{:.0}x{:.0} pixels is too large {}; lower --scale, --min-scale or the image size", width, height, limit))
+    };
+    if options.gif.is_some() && width.max(height) > GIF_MAX_SIDE as f64 {
+        return too_large(format!("for a GIF (at most {} pixels per side)", GIF_MAX_SIDE));
+    }
+    if width * height > MAX_PIXELS as f64 {
+        return too_large(format!("(at most {} pixels per image)", MAX_PIXELS));
+    }
+    Ok(())
+}
+
+// Image generating code - assisted by copilot initially
+/// Convert the grid into a scaled raster by nearest-neighbor sampling, which duplicates pixels when scaling up
+/// and skips cells when scaling down.
+fn scaled_raster_from_grid(grid: &Grid<Cell>, options: &AnimationOptions) -> Raster {
+    let height = grid.height() as u32;
+    let width = grid.width() as u32;
+    let (scaled_w, scaled_h) = image_size(width, height, options);
+    let (scaled_w, scaled_h) = (scaled_w as u32, scaled_h as u32);
+
+    if grid.is_empty() {
+        return Raster::filled(scaled_w, scaled_h, ink(Cell::Empty));
+    }
+
+    // source cell of every output column/row, and whether that pixel starts a new cell
+    let sample = |pixels: u32, cells: u32| -> Vec<(usize, bool)> {
+        (0..pixels as u64)
+            .map(|p| {
+                let cell = p * cells as u64 / pixels as u64;
+                let starts = p > 0 && (p - 1) * cells as u64 / pixels as u64 != cell;
+                (cell as usize, starts)
+            })
+            .collect()
+    };
+    let cols = sample(scaled_w, width);
+    let rows = sample(scaled_h, height);
+    let gridlines = options.gridlines && scale_for(width, height, options) >= 3.0;
+
+    let mut pixels = Vec::with_capacity(scaled_w as usize * scaled_h as usize);
+    for &(row, row_starts) in &rows {
+        for &(col, col_starts) in &cols {
+            pixels.push(if gridlines && (row_starts || col_starts) { GRIDLINE_INK } else { ink(grid[(row, col)]) });
+        }
+    }
+
+    Raster {width: scaled_w, height: scaled_h, pixels}
+}
+
+/// Add a legend below a raster: one equal-width swatch per cell state, separated by thin gaps.
+fn with_legend(raster: Raster) -> Raster {
+    let (width, height) = (raster.width, raster.height);
+    let states = FADE_STEPS as u32 + 2;
+    let strip_h = legend_height(height);
+    let swatch_w = (width / states).max(1);
+
+    let mut out = Raster::filled(width, height + strip_h, LEGEND_GAP_INK);
+    out.pixels[..raster.pixels.len()].copy_from_slice(&raster.pixels);
+
+    // legend order is ink order, so swatch i is drawn with ink i
+    for state in 0..states {
+        let x0 = state * swatch_w;
+        for x in (x0 + 1)..(x0 + swatch_w).min(width) {
+            for y in (height + 1)..(height + strip_h - 1) {
+                out.pixels[y as usize * width as usize + x as usize] = state as u8;
+            }
+        }
+    }
+
+    out
+}
+
+/// Render a frame the way it's written to disk: scaled and optionally with a legend.
+pub fn render_frame(grid: &Grid<Cell>, options: &AnimationOptions) -> Raster {
+    let raster = scaled_raster_from_grid(grid, options);
+    if options.legend {
+        with_legend(raster)
+    } else {
+        raster
+    }
+}
+
+/// Save a single frame as a scaled PNG.
+pub fn save_image(grid: &Grid<Cell>, path: &Path, options: &AnimationOptions) -> Result<(), Box<dyn Error>> {
+    check_size(grid, options)?;
+    let img = render_frame(grid, options).to_image(&options.palette);
+    img.save(path)?;
+    Ok(())
+}
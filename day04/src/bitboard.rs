@@ -0,0 +1,147 @@
+use crate::rolls::RollMap;
+use crate::rule::{Boundary, Landing};
+
+/// The map packed 64 cells to a word, row by row; bit `c % 64` of a row's word `c / 64` is column `c`. Only rows
+/// holding rolls are stored, so a huge, mostly empty map from the sparse backend doesn't cost a bit for every cell.
+struct Bitboard {
+    words_per_row: usize,
+    // rows holding at least one roll, ascending, with their words one row after another in `bits`
+    rows: Vec<usize>,
+    bits: Vec<u64>,
+}
+
+impl Bitboard {
+    fn new(rolls: &dyn RollMap) -> Self {
+        let words_per_row = rolls.width().div_ceil(64);
+        let mut rows: Vec<usize> = Vec::new();
+        let mut bits = Vec::new();
+        for &(row, col) in rolls.positions() {
+            if rows.last() != Some(&row) {
+                rows.push(row);
+                bits.resize(bits.len() + words_per_row, 0);
+            }
+            let start = bits.len() - words_per_row;
+            bits[start + col / 64] |= 1 << (col % 64);
+        }
+        Self {words_per_row, rows, bits}
+    }
+
+    // Words of `row`, `None` if it holds no rolls
+    fn row(&self, row: usize) -> Option<&[u64]> {
+        let i = self.rows.binary_search(&row).ok()?;
+        Some(&self.bits[i * self.words_per_row..(i + 1) * self.words_per_row])
+    }
+
+    fn get(&self, row: usize, col: usize) -> bool {
+        self.row(row).is_some_and(|words| words[col / 64] >> (col % 64) & 1 == 1)
+    }
+}
+
+fn set_bit(words: &mut [u64], col: usize, value: bool) {
+    if value {
+        words[col / 64] |= 1 << (col % 64);
+    } else {
+        words[col / 64] &= !(1 << (col % 64));
+    }
+}
+
+// `out[c] = row[c + shift]` for every column, with zeros where `c + shift` falls outside `0..width`
+fn shift_row(row: &[u64], width: usize, shift: isize, out: &mut [u64]) {
+    let words = row.len();
+    let word_shift = shift.unsigned_abs() / 64;
+    let bit_shift = shift.unsigned_abs() % 64;
+    let word = |i: isize| if (0..words as isize).contains(&i) { row[i as usize] } else { 0 };
+
+    for (i, out) in out.iter_mut().enumerate() {
+        let i = i as isize;
+        // higher columns move down
+        *out = if shift >= 0 {
+            let from = i + word_shift as isize;
+            match bit_shift {
+                0 => word(from),
+                b => word(from) >> b | word(from + 1) << (64 - b),
+            }
+        } else {
+            let from = i - word_shift as isize;
+            match bit_shift {
+                0 => word(from),
+                b => word(from) << b | word(from - 1) >> (64 - b),
+            }
+        };
+    }
+
+    // clear the padding past the last column that a left shift may have moved bits into
+    if !width.is_multiple_of(64) && let Some(last) = out.last_mut() {
+        *last &= (1 << (width % 64)) - 1;
+    }
+}
+
+/// Neighbor count of every roll (by roll number), computed a whole row of 64-cell words at a time.
+///
+/// Each neighbor offset contributes the map shifted by that offset, and the shifted rows are summed with
+/// bit-sliced adders: plane `p` holds bit `p` of every cell's count, so one word operation adds 64 cells.
+/// Only the few columns whose neighbor lies past the left or right edge go through the boundary cell by cell.
+pub fn neighbor_counts(rolls: &dyn RollMap, offsets: &[(isize, isize)], boundary: Boundary) -> Vec<usize> {
+    let (width, height) = (rolls.width(), rolls.height());
+    let board = Bitboard::new(rolls);
+    let words = board.words_per_row;
+
+    let plane_count = (usize::BITS - offsets.len().leading_zeros()) as usize;
+    let mut planes = vec![vec![0u64; words]; plane_count];
+    let mut shifted = vec![0u64; words];
+    let mut counts = vec![0; rolls.positions().len()];
+    let mut next_roll = 0;
+
+    for row in 0..height {
+        // rows without rolls need no counts
+        if rolls.positions().get(next_roll).is_none_or(|&(r, _)| r != row) {
+            continue;
+        }
+
+        planes.iter_mut().for_each(|plane| plane.fill(0));
+        for &(dr, dc) in offsets {
+            // the row every column of this one reaches through (dr, dc), if it lies inside the grid
+            match boundary.land((row, 0), (dr, 0), height, width) {
+                Landing::Cell(source, _) => match board.row(source) {
+                    Some(words) => shift_row(words, width, dc, &mut shifted),
+                    None => shifted.fill(0),
+                },
+                Landing::Wall => shifted.fill(u64::MAX),
+                Landing::Outside => shifted.fill(0),
+            }
+
+            let edge = if dc >= 0 {
+                width.saturating_sub(dc as usize)..width
+            } else {
+                0..dc.unsigned_abs().min(width)
+            };
+            for col in edge {
+                let value = match boundary.land((row, col), (dr, dc), height, width) {
+                    Landing::Cell(r, c) => board.get(r, c),
+                    Landing::Wall => true,
+                    Landing::Outside => false,
+                };
+                set_bit(&mut shifted, col, value);
+            }
+
+            // add the shifted row into the counts, carrying from plane to plane
+            for (i, &bits) in shifted.iter().enumerate() {
+                let mut carry = bits;
+                for plane in planes.iter_mut() {
+                    let sum = plane[i] ^ carry;
+                    carry &= plane[i];
+                    plane[i] = sum;
+                }
+            }
+        }
+
+        while let Some(&(r, col)) = rolls.positions().get(next_roll) && r == row {
+            counts[next_roll] = planes.iter().enumerate()
+                .map(|(p, plane)| ((plane[col / 64] >> (col % 64) & 1) as usize) << p)
+                .sum();
+            next_roll += 1;
+        }
+    }
+
+    counts
+}
@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use png::{BitDepth, BlendOp, ColorType, Encoder, Writer};
+
+use crate::animation::{self, AnimationOptions, Raster, UNCHANGED_INK};
+use crate::export::{AnimationFrame, Exporter};
+
+/// Animated PNG, using the same palette and delta-rectangle scheme as the GIF exporter.
+///
+/// APNG stores the frame count before the first frame, so it has to be given up front; frames are then
+/// written as they come, only the previous full frame being kept for comparison.
+pub struct ApngExporter {
+    writer: Writer<BufWriter<File>>,
+    previous: Option<Raster>,
+}
+
+impl ApngExporter {
+    pub fn create(path: &Path, first: &Raster, frame_count: usize, options: &AnimationOptions) -> Result<Box<dyn Exporter>, Box<dyn Error>> {
+        let mut palette: Vec<u8> = animation::inks(&options.palette).iter().flat_map(|rgb| rgb.0).collect();
+        palette.extend([0, 0, 0]);
+
+        // every ink is opaque except the one marking pixels unchanged since the previous frame
+        let mut alpha = vec![255; UNCHANGED_INK as usize + 1];
+        alpha[UNCHANGED_INK as usize] = 0;
+
+        let mut encoder = Encoder::new(BufWriter::new(File::create(path)?), first.width, first.height);
+        encoder.set_color(ColorType::Indexed);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_palette(palette);
+        encoder.set_trns(alpha);
+        encoder.set_animated(frame_count as u32, options.num_plays())?;
+
+        Ok(Box::new(Self {writer: encoder.write_header()?, previous: None}))
+    }
+}
+
+impl Exporter for ApngExporter {
+    fn write_frame(&mut self, frame: &AnimationFrame) -> Result<(), Box<dyn Error>> {
+        let raster = frame.raster;
+        let (left, top, width, height) = match &self.previous {
+            None => (0, 0, raster.width, raster.height),
+            Some(prev) => raster.changed_rect(prev).unwrap_or((0, 0, 1, 1)),
+        };
+
+        // the position is reset first so the new size is never checked against the previous frame's offset
+        self.writer.reset_frame_position()?;
+        self.writer.set_frame_dimension(width, height)?;
+        self.writer.set_frame_position(left, top)?;
+        self.writer.set_frame_delay(frame.delay_ms, 1000)?;
+        self.writer.set_blend_op(BlendOp::Over)?;
+        self.writer.write_image_data(&raster.crop((left, top, width, height), self.previous.as_ref()))?;
+
+        self.previous = Some(raster.clone());
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::animation::AnimationOptions;
+use crate::export::{AnimationFrame, Exporter};
+use crate::palette::Palette;
+
+/// asciinema recording (asciicast v2): every frame redraws the grid in place, two colored spaces per cell.
+pub struct AsciicastExporter {
+    out: BufWriter<File>,
+    palette: Palette,
+    // seconds since the start of the recording at which the next frame appears
+    time: f64,
+}
+
+impl AsciicastExporter {
+    pub fn create(path: &Path, (width, height): (usize, usize), options: &AnimationOptions) -> Result<Box<dyn Exporter>, Box<dyn Error>> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let header = json!({"version": 2, "width": width * 2, "height": height, "env": {"TERM": "xterm-256color"}});
+        writeln!(out, "{}", header)?;
+        writeln!(out, "{}", json!([0.0, "o", "\x1b[2J"]))?;
+
+        Ok(Box::new(Self {out, palette: options.palette.clone(), time: 0.0}))
+    }
+}
+
+impl Exporter for AsciicastExporter {
+    fn write_frame(&mut self, frame: &AnimationFrame) -> Result<(), Box<dyn Error>> {
+        let mut screen = String::from("\x1b[H");
+
+        for (y, row) in frame.cells.rows().enumerate() {
+            if y > 0 {
+                screen.push_str("\r\n");
+            }
+
+            // only switch background color when it changes along the row
+            let mut current = None;
+            for &cell in row {
+                let color = self.palette.color(cell);
+                if current != Some(color) {
+                    write!(screen, "\x1b[48;2;{};{};{}m", color[0], color[1], color[2])?;
+                    current = Some(color);
+                }
+                screen.push_str("  ");
+            }
+            screen.push_str("\x1b[0m");
+        }
+
+        writeln!(self.out, "{}", json!([self.time, "o", screen]))?;
+        self.time += frame.delay_ms as f64 / 1000.0;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        // a final empty event keeps the last frame on screen for its full delay
+        writeln!(self.out, "{}", json!([self.time, "o", ""]))?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
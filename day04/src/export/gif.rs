@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+
+use crate::animation::{self, AnimationOptions, Raster, UNCHANGED_INK};
+use crate::export::{AnimationFrame, Exporter};
+
+/// Animated GIF (no temp files).
+///
+/// All frames share one global palette made of the known inks, so no per-frame color quantization is needed,
+/// and after the first frame only the rectangle that changed since the previous frame is encoded, with the
+/// unchanged pixels inside it left transparent so they compress to almost nothing.
+pub struct GifExporter {
+    encoder: Encoder<BufWriter<File>>,
+    previous: Option<Raster>,
+}
+
+impl GifExporter {
+    pub fn create(path: &Path, first: &Raster, options: &AnimationOptions) -> Result<Box<dyn Exporter>, Box<dyn Error>> {
+        // All frames must be the same size, so the first one decides the GIF dimensions
+        let (gif_w, gif_h) = match (u16::try_from(first.width), u16::try_from(first.height)) {
+            (Ok(w), Ok(h)) => (w, h),
+            _ => return Err(format!("{}x{} pixels is too large for a GIF", first.width, first.height).into()),
+        };
+
+        let mut global_palette: Vec<u8> = animation::inks(&options.palette).iter().flat_map(|rgb| rgb.0).collect();
+        global_palette.extend([0, 0, 0]);
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = Encoder::new(file, gif_w, gif_h, &global_palette)?;
+        encoder.set_repeat(options.repeats().map_or(Repeat::Infinite, Repeat::Finite))?;
+
+        Ok(Box::new(Self {encoder, previous: None}))
+    }
+}
+
+impl Exporter for GifExporter {
+    fn write_frame(&mut self, frame: &AnimationFrame) -> Result<(), Box<dyn Error>> {
+        let raster = frame.raster;
+
+        // a frame identical to the previous one still needs a (1x1) image to carry its delay
+        let rect = match &self.previous {
+            None => (0, 0, raster.width, raster.height),
+            Some(prev) => raster.changed_rect(prev).unwrap_or((0, 0, 1, 1)),
+        };
+
+        let gif_frame = Frame {
+            left: rect.0 as u16,
+            top: rect.1 as u16,
+            width: rect.2 as u16,
+            height: rect.3 as u16,
+            buffer: Cow::Owned(raster.crop(rect, self.previous.as_ref())),
+            transparent: self.previous.as_ref().map(|_| UNCHANGED_INK),
+            // GIF uses 1/100s units
+            delay: frame.delay_ms / 10,
+            dispose: DisposalMethod::Keep,
+            ..Frame::default()
+        };
+        self.encoder.write_frame(&gif_frame)?;
+
+        self.previous = Some(raster.clone());
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        let mut file = self.encoder.into_inner()?;
+        std::io::Write::flush(&mut file)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use std::error::Error;
+use std::path::Path;
+
+use common::Grid;
+
+use crate::animation::{self, AnimationOptions, Raster};
+use crate::matrix::Simulation;
+use crate::render::{self, Cell};
+
+mod apng;
+mod asciicast;
+mod gif;
+mod png_frames;
+
+/// A single frame of the animation, handed to every exporter in order.
+pub struct AnimationFrame<'a> {
+    pub cells: &'a Grid<Cell>,
+    /// The frame rasterized to ink indices, shared by the image based exporters.
+    pub raster: &'a Raster,
+    pub delay_ms: u16,
+}
+
+/// An animation output format.
+pub trait Exporter {
+    fn write_frame(&mut self, frame: &AnimationFrame) -> Result<(), Box<dyn Error>>;
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>>;
+}
+
+// Prefix an exporter's errors with the file it was writing
+fn in_file<T>(path: &Path, result: Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+    result.map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Streams frames to every output requested in `options` as they are produced.
+///
+/// Only the most recent frame is kept in memory: it's held back until the next one arrives, because the last
+/// frame is shown for `hold_end_ms` and that's only known once `finish` is called. APNG needs the frame count
+/// before its first frame, so it isn't streamed: `finish` renders its frames again from the finished simulation,
+/// once the count is known, and writes each straight to the file.
+pub struct AnimationWriter<'a> {
+    options: &'a AnimationOptions,
+    exporters: Vec<(&'a Path, Box<dyn Exporter>)>,
+    pending: Option<(usize, Grid<Cell>, Raster)>,
+    frame_count: usize,
+}
+
+impl<'a> AnimationWriter<'a> {
+    /// Outputs are only opened once the first frame arrives, since its size decides theirs.
+    pub fn new(options: &'a AnimationOptions) -> Self {
+        Self {options, exporters: Vec::new(), pending: None, frame_count: 0}
+    }
+
+    // Open every requested output; all later frames must have the same size as `first`
+    fn open(&mut self, first: &Grid<Cell>, raster: &Raster) -> Result<(), Box<dyn Error>> {
+        let options = self.options;
+        if let Some(path) = &options.gif {
+            self.exporters.push((path, in_file(path, gif::GifExporter::create(path, raster, options))?));
+        }
+        if let Some(path) = &options.png_frames {
+            self.exporters.push((path, in_file(path, png_frames::PngFramesExporter::create(path, options))?));
+        }
+        if let Some(path) = &options.asciicast {
+            self.exporters.push((path, in_file(path, asciicast::AsciicastExporter::create(path, (first.width(), first.height()), options))?));
+        }
+        Ok(())
+    }
+
+    /// Whether any output was requested at all.
+    pub fn is_requested(options: &AnimationOptions) -> bool {
+        options.gif.is_some() || options.apng.is_some() || options.png_frames.is_some() || options.asciicast.is_some()
+    }
+
+    // Whether any output takes frames while the simulation runs, i.e. anything but APNG
+    fn streams(&self) -> bool {
+        self.options.gif.is_some() || self.options.png_frames.is_some() || self.options.asciicast.is_some()
+    }
+
+    /// Add the frame for `wave`, which is only rendered if the wave is sampled (see `AnimationOptions::every`)
+    /// and some output is streamed. Wave 0 is always sampled, so the first frame is the initial grid.
+    pub fn add_wave<F>(&mut self, wave: usize, frame: F) -> Result<(), Box<dyn Error>>
+    where F: FnOnce() -> Grid<Cell>, {
+        if !wave.is_multiple_of(self.options.every) || !self.streams() {
+            return Ok(());
+        }
+        self.push(wave, frame())
+    }
+
+    // Every wave that gets a frame, ending with `last`
+    fn sampled_waves(&self, last: usize) -> Vec<usize> {
+        (0..last).filter(|wave| wave.is_multiple_of(self.options.every)).chain([last]).collect()
+    }
+
+    fn push(&mut self, wave: usize, cells: Grid<Cell>) -> Result<(), Box<dyn Error>> {
+        // every frame has the size of the first, so checking that one before rasterizing covers them all
+        if self.pending.is_none() {
+            animation::check_size(&cells, self.options)?;
+        }
+        let raster = animation::render_frame(&cells, self.options);
+        if self.pending.is_none() {
+            self.open(&cells, &raster)?;
+        }
+
+        if let Some(previous) = self.pending.replace((wave, cells, raster)) {
+            let delay_ms = match self.frame_count {
+                0 => self.options.hold_start_ms,
+                _ => self.options.delay_ms,
+            };
+            self.write(&previous, delay_ms)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, (_, cells, raster): &(usize, Grid<Cell>, Raster), delay_ms: u16) -> Result<(), Box<dyn Error>> {
+        let frame = AnimationFrame {cells, raster, delay_ms};
+        for (path, exporter) in self.exporters.iter_mut() {
+            in_file(path, exporter.write_frame(&frame))?;
+        }
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    /// Write the fade-out after the last removal wave, ending with the fully faded frame (even when it isn't
+    /// sampled), then the APNG if requested, and close every output. Returns the number of frames written.
+    pub fn finish(mut self, sim: &Simulation) -> Result<usize, Box<dyn Error>> {
+        let faded = sim.faded_wave();
+        for wave in sim.removals.len() + 1..faded {
+            self.add_wave(wave, || render::frame(sim, wave))?;
+        }
+        if self.pending.as_ref().is_some_and(|(pending_wave, _, _)| *pending_wave != faded) {
+            self.push(faded, render::frame(sim, faded))?;
+        }
+
+        if let Some(last) = self.pending.take() {
+            let delay_ms = match self.frame_count {
+                0 => self.options.hold_start_ms,
+                _ => self.options.hold_end_ms,
+            };
+            self.write(&last, delay_ms)?;
+        }
+
+        for (path, exporter) in self.exporters.drain(..) {
+            in_file(path, exporter.finish())?;
+        }
+
+        match &self.options.apng {
+            Some(path) => in_file(path, self.write_apng(path, sim)),
+            None => Ok(self.frame_count),
+        }
+    }
+
+    // Same frames and delays as the streamed outputs, rendered one at a time
+    fn write_apng(&self, path: &Path, sim: &Simulation) -> Result<usize, Box<dyn Error>> {
+        let waves = self.sampled_waves(sim.faded_wave());
+        let mut exporter: Option<Box<dyn Exporter>> = None;
+
+        for (i, &wave) in waves.iter().enumerate() {
+            let cells = render::frame(sim, wave);
+            if i == 0 {
+                animation::check_size(&cells, self.options)?;
+            }
+            let raster = animation::render_frame(&cells, self.options);
+            let exporter = match &mut exporter {
+                Some(exporter) => exporter,
+                None => exporter.insert(apng::ApngExporter::create(path, &raster, waves.len(), self.options)?),
+            };
+
+            let delay_ms = match i {
+                0 => self.options.hold_start_ms,
+                i if i == waves.len() - 1 => self.options.hold_end_ms,
+                _ => self.options.delay_ms,
+            };
+            exporter.write_frame(&AnimationFrame {cells: &cells, raster: &raster, delay_ms})?;
+        }
+
+        if let Some(exporter) = exporter {
+            exporter.finish()?;
+        }
+        Ok(waves.len())
+    }
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::animation::AnimationOptions;
+use crate::export::{AnimationFrame, Exporter};
+use crate::palette::Palette;
+
+/// Every frame as its own numbered PNG (`frame_00000.png`, ...) in a directory, e.g. for video tools.
+pub struct PngFramesExporter {
+    dir: PathBuf,
+    palette: Palette,
+    next: usize,
+}
+
+impl PngFramesExporter {
+    pub fn create(dir: &Path, options: &AnimationOptions) -> Result<Box<dyn Exporter>, Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        Ok(Box::new(Self {dir: dir.to_path_buf(), palette: options.palette.clone(), next: 0}))
+    }
+}
+
+impl Exporter for PngFramesExporter {
+    fn write_frame(&mut self, frame: &AnimationFrame) -> Result<(), Box<dyn Error>> {
+        let path = self.dir.join(format!("frame_{:05}.png", self.next));
+        frame.raster.to_image(&self.palette).save(path)?;
+        self.next += 1;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
@@ -1,9 +1,27 @@
-
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
-use gif::{Encoder, Frame, Repeat};
-use image::{ImageBuffer, Rgb};
+
+mod analysis;
+mod bitboard;
+#[cfg(feature = "animation")]
+mod animation;
+#[cfg(feature = "animation")]
+mod export;
+mod matrix;
+mod options;
+#[cfg(any(feature = "animation", feature = "tui"))]
+mod palette;
+mod render;
+mod report;
+mod rolls;
+mod rule;
+#[cfg(feature = "tui")]
+mod tui;
+
+use matrix::RollMatrix;
+use options::Options;
+use report::Report;
 
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where P: AsRef<Path>, {
@@ -11,231 +29,152 @@ where P: AsRef<Path>, {
     Ok(io::BufReader::new(file).lines())
 }
 
-#[derive(Clone)]
-struct RollMatrix {
-    rows: Vec<Vec<char>>,
-    roll_indicator: char,
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
 
-    // added for animation generation
-    frames: Vec<Vec<Vec<char>>>,
-}
+    // only roll positions are kept, so huge but mostly empty maps stay small with the sparse backend
+    let mut positions: Vec<(usize, usize)> = Vec::new();
+    let mut row_lengths: Vec<usize> = Vec::new();
 
-impl RollMatrix {
-    fn new(roll_indicator: char) -> Self {
-        Self {rows: Vec::new(), roll_indicator, frames: Vec::new()}
+    if let Ok(lines) = read_lines("./input") {
+        for (row, line) in lines.map_while(Result::ok).enumerate() {
+            positions.extend(line.chars().enumerate().filter(|&(_, c)| c == '@').map(|(col, _)| (row, col)));
+            row_lengths.push(line.chars().count());
+        }
     }
 
-    fn push(&mut self, row: Vec<char>) {
-        self.rows.push(row);
+    // short rows are treated as ending in empty cells rather than rejected
+    let width = row_lengths.iter().copied().max().unwrap_or(0);
+    let short_rows = row_lengths.iter().filter(|&&len| len < width).count();
+    if short_rows > 0 {
+        eprintln!("Warning: padded {} short row(s) to {} cells with empty space", short_rows, width);
     }
-
-    fn check_at(&self, row_index: usize, row_offset: isize, col_index: usize, col_offset: isize) -> u64 {
-        row_index.checked_add_signed(row_offset)
-            .and_then(|r| self.rows.get(r))
-            .and_then(|row| {
-                col_index.checked_add_signed(col_offset)
-                .and_then(|c| row.get(c))
-            })
-            .map(|elem| (*elem == self.roll_indicator) as u64)
-            .unwrap_or(0)
-        }
-        
-    fn count_movable_rolls(&mut self, single: bool) -> u64 {
-        let mut cnt: u64 = 0;
-        let mut weights = self.rows.clone();       
-        let mut stuck_cnt = 0;
-
-        loop  {
-            self.frames.push(self.rows.clone());
-
-            let mut new_cnt = 0;
-            for (j, row) in self.rows.iter().enumerate() {
-                for (i, roll) in row.iter().enumerate() {
-                    if roll != &self.roll_indicator {
-                        //print!("{}", roll);
-                        continue;
-                    } else {
-                        let mut near = 0;
-                        
-                        near += self.check_at(j, -1, i, -1);    //check top-left
-                        near += self.check_at(j, -1, i, 0);     //check above
-                        near += self.check_at(j, -1, i, 1);     //check top-right                    
-                        near += self.check_at(j, 0, i, -1);     //check left
-                        near += self.check_at(j, 0, i, 1);      //check right
-                        near += self.check_at(j, 1, i, -1);     //check bottom-left
-                        near += self.check_at(j, 1, i, 0);      //check bottom
-                        near += self.check_at(j, 1, i, 1);      //check bottom-right
-
-                        weights[j][i] = near.to_string().chars().nth(0).unwrap_or('0');
-                        //print!("{}",  roll);
-                    }
-                }
-                //println!();
-            }
-            println!();
-        
-            for row in weights.iter_mut() {
-                for col in row.iter_mut() {
-                    let new_cell = match *col {
-                        c if c.is_digit(10) => {
-                            if c.to_digit(10).unwrap_or(u32::MAX) < 4 {
-                                new_cnt += 1;
-                                'X'
-                            } else {
-                                '@'
-                            }
-                        }
-                        'X' => 'x',
-                        'x' => 'Y',
-                        'Y' => 'y',
-                        'y' => 'Z',
-                        'Z' => 'z',
-                        'z' => 'A',
-                        'A' => 'a',
-                        'a' => '.',
-                        other => other,
-                    };
-                    *col = new_cell;
-                    print!("{}", col);
-                }
-                println!();
-            }
-            
-            cnt += new_cnt;
-            println!("Rolls removed: {}", new_cnt);
-
-            if new_cnt == 0 {
-                stuck_cnt += 1;
-            } else {
-                stuck_cnt = 0;
+    let rolls = options.backend.build(width, row_lengths.len(), positions);
+    let rm = RollMatrix::new(rolls, options.backend, options.rule).with_counter(options.counter);
+
+    if options.verify {
+        match rm.verify_counts() {
+            Ok(checked) => println!("Bitboard neighbor counts match the naive ones for all {} rolls", checked),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
             }
+        }
+    }
 
-            // Only run once for original day 1 puzzle, otherwise continue to modify matrix (but run 8 extra times for animation color transitions)
-            if single || stuck_cnt == 8 {
-                break;
-            }
+    // part 1: a single wave of removals
+    let part1 = rm.simulate(Some(1));
+    if options.print_grids {
+        print!("{}", render::frame(&part1, 1));
+        println!("Rolls removed: {}", part1.total_removed());
+    }
+    println!("Total movable rolls: {}", part1.total_removed());
+
+    // part 2: keep removing until nothing else is accessible, streaming frames to any animation outputs
+    #[cfg(feature = "animation")]
+    let mut writer = export::AnimationWriter::is_requested(&options.animation).then(|| {
+        println!("Generating animation...");
+        export::AnimationWriter::new(&options.animation)
+    });
+
+    #[cfg(feature = "animation")]
+    let part2 = rm.simulate_with(None, |wave, sim| {
+        if let Some(writer) = writer.as_mut() && let Err(e) = writer.add_wave(wave, || render::frame(sim, wave)) {
+            animation_failed(e);
+        }
+    });
+    #[cfg(not(feature = "animation"))]
+    let part2 = rm.simulate(None);
 
-            self.rows = weights.clone();
+    if options.print_grids {
+        for (wave, removed) in part2.removals.iter().enumerate() {
+            println!();
+            print!("{}", render::frame(&part2, wave + 1));
+            println!("Rolls removed: {}", removed);
         }
+    }
 
-        //self.save_image("test.png", 1000);
-        if !single {
-            self.frames.push(self.rows.clone());
-            println!("Generating animation using {} frames...", self.frames.len());
-            self.save_gif("animation.gif", 1000, 75);
+    #[cfg(feature = "tui")]
+    if options.tui {
+        // --palette is an animation option, so without that feature the viewer sticks to the default colors
+        #[cfg(feature = "animation")]
+        let palette = &options.animation.palette;
+        #[cfg(not(feature = "animation"))]
+        let palette = &palette::Palette::heat();
+
+        if let Err(e) = tui::run(&part2, palette) {
+            eprintln!("Terminal viewer failed: {}", e);
+            std::process::exit(1);
         }
+    }
+
+    let report = Report::new(&part2);
+    report.print_summary();
 
-        cnt
+    if let Some(path) = &options.json && let Err(e) = report.save_json(path) {
+        eprintln!("Could not write {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    if let Some(path) = &options.csv && let Err(e) = report.save_csv(path) {
+        eprintln!("Could not write {}: {}", path.display(), e);
+        std::process::exit(1);
     }
 
-    // Image generating code - assisted by copilot initially
-    /// Convert the grid into a scaled image using pixel duplication.
-    fn scaled_image_from_grid(
-        grid: &[Vec<char>],
-        target_size: u32
-    ) -> ImageBuffer<Rgb<u8>, Vec<u8>> 
-    {
-        let height = grid.len() as u32;
-        let width = grid[0].len() as u32;
-
-        let scale = target_size / width.max(height);
-        let scaled_w = width * scale;
-        let scaled_h = height * scale;
-
-        let mut img = ImageBuffer::new(scaled_w, scaled_h);
-
-        for (y, row) in grid.iter().enumerate() {
-            for (x, &cell) in row.iter().enumerate() {
-                let color = match cell {
-                    '@' => Rgb([200, 200, 200]),
-                    'X' => Rgb([255, 242, 116]),
-                    'x' => Rgb([255, 199, 92]),
-                    'Y' => Rgb([255, 153, 67]),
-                    'y' => Rgb([255, 102, 41]),
-                    'Z' => Rgb([255, 0, 0]),
-                    'z' => Rgb([174, 0, 0]),
-                    'A' => Rgb([99, 0, 0]),
-                    'a' => Rgb([34, 0, 0]),
-                    '.' => Rgb([0, 0, 0]),
-                    _   => Rgb([128, 128, 128]),
-                };
-
-                for dy in 0..scale {
-                    for dx in 0..scale {
-                        img.put_pixel(
-                            (x as u32 * scale) + dx,
-                            (y as u32 * scale) + dy,
-                            color,
-                        );
-                    }
-                }
+    if options.core {
+        println!("Stable core:");
+        print!("{}", analysis::core(&part2));
+    }
+    if let Some((row, col)) = options.protect {
+        match analysis::protect(&rm, (row, col)) {
+            Ok(Some(added)) if added.is_empty() => println!("Roll at ({}, {}) is never removed", row, col),
+            Ok(Some(added)) => {
+                let cells: Vec<String> = added.iter().map(|(r, c)| format!("({}, {})", r, c)).collect();
+                println!("Roll at ({}, {}) is never removed after adding {} roll(s) at {}", row, col, added.len(), cells.join(", "));
+            }
+            Ok(None) => println!("Even filling every empty cell doesn't keep the roll at ({}, {}) from being removed", row, col),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
             }
         }
-
-        img
     }
-
-    /// Save the current grid as a scaled PNG.
-    #[allow(dead_code)]
-    fn save_image(&self, path: &str, target_size: u32) {
-        let img = Self::scaled_image_from_grid(&self.rows, target_size);
-        img.save(path).unwrap();
+    if let Some((row, col)) = options.remove_first {
+        match analysis::remove_first(&rm, (row, col), &part2) {
+            Ok(cascade) => println!(
+                "Removing ({}, {}) first: {} rolls removed instead of {} ({:+}), {} of them from the stable core",
+                row, col, cascade.removed, cascade.baseline, cascade.removed as i64 - cascade.baseline as i64, cascade.core_lost,
+            ),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
     }
 
-    /// Save a sequence of RollMatrix frames as a GIF (no temp files).
-    fn save_gif(&self, path: &str, target_size: u32, delay_ms: u16) {
-        // All frames must be the same size
-        let base_w = self.frames[0][0].len() as u32;
-        let base_h = self.frames[0].len() as u32;
-
-        let scale = target_size / base_w.max(base_h);
-        let gif_w = base_w * scale;
-        let gif_h = base_h * scale;
-
-        let mut file = std::fs::File::create(path).unwrap();
-        let mut encoder = Encoder::new(&mut file, gif_w as u16, gif_h as u16, &[]).unwrap();
-        encoder.set_repeat(Repeat::Infinite).unwrap();
-
-        for (idx, frame) in self.frames.iter().enumerate() {
-            let img = Self::scaled_image_from_grid(frame, target_size);
-
-            // Convert to raw RGB for GIF
-            let mut rgb_data = Vec::with_capacity((gif_w * gif_h * 3) as usize);
-            for pixel in img.pixels() {
-                rgb_data.extend_from_slice(&pixel.0);
+    #[cfg(feature = "animation")]
+    {
+        // the fade-out after the last wave only depends on the finished removal waves
+        if let Some(writer) = writer {
+            match writer.finish(&part2) {
+                Ok(count) => println!("Animation has {} frames", count),
+                Err(e) => animation_failed(e),
             }
-
-            let mut gif_frame = Frame::from_rgb(gif_w as u16, gif_h as u16, &rgb_data);
-            
-            // GIF uses 1/100s units
-            let delay = match idx {
-                0 => 200,
-                n if n == self.frames.len() - 1 => 200,
-                _ => delay_ms / 10,
-            };
-            gif_frame.delay = delay;
-
-            encoder.write_frame(&gif_frame).unwrap();
         }
-    }
-}
 
-fn main() {
-    //process 3 rows at a time in a moving window, where initally the first row is null, and at the end the last row is null
-    let mut rm: RollMatrix = RollMatrix::new('@');
-    let mut total_movable_rolls_part1 = 0;
-    let mut total_movable_rolls_part2 = 0;
-    
-    // insert rows from input, when we reach 3 total, begin checking for movable rolls at position 2 in the 3-row moving window
-    if let Ok(lines) = read_lines("./input") {
-        for row in lines.map_while(Result::ok) {            
-            rm.push(row.chars().collect());
+        if let Some(path) = &options.animation.png && let Err(e) = animation::save_image(&render::frame(&part2, part2.faded_wave()), path, &options.animation) {
+            eprintln!("Could not write {}: {}", path.display(), e);
+            std::process::exit(1);
         }
     }
 
-    total_movable_rolls_part1 += rm.count_movable_rolls(true);
-    println!("Total movable rolls: {}", total_movable_rolls_part1);
-    
-    total_movable_rolls_part2 += rm.count_movable_rolls(false);
-    println!("Total movable rolls: {}", total_movable_rolls_part2);
+    println!("Total movable rolls: {}", part2.total_removed());
+}
+
+#[cfg(feature = "animation")]
+fn animation_failed(e: Box<dyn std::error::Error>) -> ! {
+    eprintln!("Could not write animation: {}", e);
+    std::process::exit(1);
 }
@@ -0,0 +1,251 @@
+use crate::bitboard;
+use crate::rolls::{Backend, RollMap};
+use crate::rule::{Boundary, Comparison, Landing, Rule};
+
+/// Map of paper rolls and the rule removing them; the removal simulation never mutates it, so each part can
+/// run independently.
+pub struct RollMatrix {
+    rolls: Box<dyn RollMap>,
+    backend: Backend,
+    rule: Rule,
+    counter: Counter,
+
+    // neighbor offsets of the rule, and offsets reaching every cell that counts a given cell as its neighbor
+    offsets: Vec<(isize, isize)>,
+    reverse_offsets: Vec<(isize, isize)>,
+}
+
+/// How the neighbor counts the simulation starts from are computed; later waves only adjust them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Counter {
+    /// Every offset of every roll looked up one at a time.
+    Naive,
+    /// Whole rows at once on a packed bitboard, see `bitboard::neighbor_counts`.
+    Bitboard,
+}
+
+impl Counter {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "naive" => Ok(Counter::Naive),
+            "bitboard" => Ok(Counter::Bitboard),
+            other => Err(format!("Unknown counter '{}' (expected naive or bitboard)", other)),
+        }
+    }
+}
+
+/// Outcome of running the removal rule wave by wave. Rolls are identified by their number in the `RollMap`.
+pub struct Simulation {
+    pub width: usize,
+    pub height: usize,
+    /// Position of every roll, in row-major order.
+    pub positions: Vec<(usize, usize)>,
+    /// Wave (1-based) in which each roll was removed, `None` for rolls that were never removed.
+    pub removed_at: Vec<Option<usize>>,
+    /// Number of rolls removed in each wave, the last wave being the last one that removed anything.
+    pub removals: Vec<u64>,
+}
+
+impl Simulation {
+    pub fn initial_rolls(&self) -> u64 {
+        self.positions.len() as u64
+    }
+
+    pub fn total_removed(&self) -> u64 {
+        self.removals.iter().sum()
+    }
+
+    /// First wave by which every removed roll has completely faded out, i.e. the final animation frame.
+    #[cfg(any(feature = "animation", feature = "tui"))]
+    pub fn faded_wave(&self) -> usize {
+        self.removals.len() + crate::render::FADE_STEPS
+    }
+
+    /// Rolls still standing when the simulation stopped, in row-major order; after a full run this is the
+    /// stable core.
+    pub fn survivors(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.positions.iter().zip(&self.removed_at)
+            .filter(|(_, removed)| removed.is_none())
+            .map(|(&pos, _)| pos)
+    }
+}
+
+impl RollMatrix {
+    pub fn new(rolls: Box<dyn RollMap>, backend: Backend, rule: Rule) -> Self {
+        let offsets = rule.neighborhood.offsets();
+
+        // negating an offset finds exactly the cell that reached us through it, except when mirroring: a
+        // reflected offset may have come from either side of the edge, so try every sign combination
+        let mut reverse_offsets: Vec<(isize, isize)> = match rule.boundary {
+            Boundary::Mirror => offsets.iter()
+                .flat_map(|&(dr, dc)| [(dr, dc), (-dr, dc), (dr, -dc), (-dr, -dc)])
+                .collect(),
+            _ => offsets.iter().map(|&(dr, dc)| (-dr, -dc)).collect(),
+        };
+        if rule.boundary == Boundary::Mirror {
+            reverse_offsets.sort_unstable();
+            reverse_offsets.dedup();
+        }
+
+        Self {rolls, backend, rule, counter: Counter::Naive, offsets, reverse_offsets}
+    }
+
+    pub fn with_counter(self, counter: Counter) -> Self {
+        Self {counter, ..self}
+    }
+
+    pub fn rolls(&self) -> &dyn RollMap {
+        &*self.rolls
+    }
+
+    /// Same rule and backend, with a roll placed at (`roll` = true) or taken away from (`false`) every cell given.
+    pub fn with_cells(&self, cells: &[(usize, usize)], roll: bool) -> Self {
+        let mut positions = self.rolls.positions().to_vec();
+        if roll {
+            positions.extend(cells);
+        } else {
+            positions.retain(|pos| !cells.contains(pos));
+        }
+        positions.sort_unstable();
+        positions.dedup();
+
+        let rolls = self.backend.build(self.rolls.width(), self.rolls.height(), positions);
+        Self {
+            rolls,
+            backend: self.backend,
+            rule: self.rule.clone(),
+            counter: self.counter,
+            offsets: self.offsets.clone(),
+            reverse_offsets: self.reverse_offsets.clone(),
+        }
+    }
+
+    // Where an offset from `pos` lands under the rule's boundary
+    fn land(&self, pos: (usize, usize), offset: (isize, isize)) -> Landing {
+        self.rule.boundary.land(pos, offset, self.rolls.height(), self.rolls.width())
+    }
+
+    // Standing neighbors of the roll at `pos`, `alive` telling which rolls are still standing
+    fn count_neighbors(&self, alive: &[bool], pos: (usize, usize)) -> usize {
+        self.offsets.iter()
+            .filter(|&&offset| match self.land(pos, offset) {
+                Landing::Cell(r, c) => self.rolls.roll_at((r, c)).is_some_and(|id| alive[id]),
+                Landing::Wall => true,
+                Landing::Outside => false,
+            })
+            .count()
+    }
+
+    /// Whether the roll at `pos` is removed in the first wave however many rolls are added: the rule removes
+    /// rolls with few neighbors, and even a roll on every cell it can count isn't enough.
+    pub fn always_accessible(&self, pos: (usize, usize)) -> bool {
+        let most = self.offsets.iter().filter(|&&offset| !matches!(self.land(pos, offset), Landing::Outside)).count();
+        matches!(self.rule.comparison, Comparison::Less | Comparison::LessEq) && self.rule.is_accessible(most)
+    }
+
+    // Neighbor count of every roll before anything is removed
+    fn initial_counts(&self) -> Vec<usize> {
+        match self.counter {
+            Counter::Naive => self.naive_counts(),
+            Counter::Bitboard => bitboard::neighbor_counts(&*self.rolls, &self.offsets, self.rule.boundary),
+        }
+    }
+
+    fn naive_counts(&self) -> Vec<usize> {
+        let alive = vec![true; self.rolls.positions().len()];
+        self.rolls.positions().iter().map(|&pos| self.count_neighbors(&alive, pos)).collect()
+    }
+
+    /// Check the bitboard counter against the naive one on this map; returns the number of rolls compared.
+    pub fn verify_counts(&self) -> Result<usize, String> {
+        let naive = self.naive_counts();
+        let fast = bitboard::neighbor_counts(&*self.rolls, &self.offsets, self.rule.boundary);
+
+        match naive.iter().zip(&fast).position(|(a, b)| a != b) {
+            Some(id) => {
+                let (row, col) = self.rolls.positions()[id];
+                Err(format!("Neighbor counts differ at ({}, {}): naive {}, bitboard {}", row, col, naive[id], fast[id]))
+            }
+            None => Ok(naive.len()),
+        }
+    }
+
+    /// Remove every accessible roll at once, repeatedly, until nothing changes or `max_waves` waves have run.
+    ///
+    /// Neighbor counts are computed once up front and then only adjusted around removed rolls; the only rolls
+    /// re-examined in a wave are those whose count changed in the previous one, so each roll is looked at
+    /// a bounded number of times and the whole run is close to linear in the number of rolls.
+    pub fn simulate(&self, max_waves: Option<usize>) -> Simulation {
+        self.simulate_with(max_waves, |_, _| {})
+    }
+
+    /// Like `simulate`, calling `on_wave` with the simulation so far once for the initial map (wave 0) and
+    /// again after every wave, so frames can be produced while the simulation runs.
+    pub fn simulate_with<F>(&self, max_waves: Option<usize>, mut on_wave: F) -> Simulation
+    where F: FnMut(usize, &Simulation), {
+        let positions = self.rolls.positions();
+        let mut sim = Simulation {
+            width: self.rolls.width(),
+            height: self.rolls.height(),
+            positions: positions.to_vec(),
+            removed_at: vec![None; positions.len()],
+            removals: Vec::new(),
+        };
+
+        let mut alive = vec![true; positions.len()];
+        let mut counts = self.initial_counts();
+        let mut candidates: Vec<usize> = (0..positions.len()).collect();
+
+        // last wave each roll was queued for, so a roll next to several removed ones is only queued once
+        let mut queued_for = vec![0; positions.len()];
+
+        on_wave(0, &sim);
+
+        while max_waves.is_none_or(|max| sim.removals.len() < max) {
+            let wave = sim.removals.len() + 1;
+
+            // decide the whole wave against the current counts before removing anything
+            let accessible: Vec<usize> = candidates.drain(..)
+                .filter(|&id| self.rule.is_accessible(counts[id]))
+                .collect();
+
+            if accessible.is_empty() {
+                break;
+            }
+
+            for &id in &accessible {
+                alive[id] = false;
+                sim.removed_at[id] = Some(wave);
+            }
+
+            // surviving rolls that counted a removed roll lose a neighbor each, and are the only rolls whose
+            // accessibility can change for the next wave
+            for &removed in &accessible {
+                for &offset in &self.reverse_offsets {
+                    let Landing::Cell(r, c) = self.land(positions[removed], offset) else { continue };
+                    let Some(id) = self.rolls.roll_at((r, c)).filter(|&id| alive[id]) else { continue };
+
+                    if self.rule.boundary != Boundary::Mirror {
+                        counts[id] -= 1;
+                    }
+                    if queued_for[id] != wave {
+                        queued_for[id] = wave;
+                        candidates.push(id);
+                    }
+                }
+            }
+
+            // mirrored offsets can't be undone one for one, so recount the candidates instead
+            if self.rule.boundary == Boundary::Mirror {
+                for &id in &candidates {
+                    counts[id] = self.count_neighbors(&alive, positions[id]);
+                }
+            }
+
+            sim.removals.push(accessible.len() as u64);
+            on_wave(wave, &sim);
+        }
+
+        sim
+    }
+}
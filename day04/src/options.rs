@@ -0,0 +1,229 @@
+#[cfg(feature = "animation")]
+use std::num::{NonZeroU16, NonZeroUsize};
+use std::path::PathBuf;
+
+#[cfg(feature = "animation")]
+use crate::animation::{self, AnimationOptions};
+#[cfg(feature = "animation")]
+use crate::palette::Palette;
+use crate::matrix::Counter;
+use crate::rolls::Backend;
+use crate::rule::{Boundary, Comparison, Neighborhood, Rule};
+
+const USAGE: &str = "\
+Usage: day04 [options]
+
+Accessibility rule (default: moore, radius 1, lt 4):
+  --neighborhood <moore|von-neumann|custom:dr,dc;...>
+  --radius <r>          radius of the moore/von-neumann neighborhood
+  --threshold <n>       neighbor count compared against
+  --compare <lt|le|eq|ge|gt>
+  --boundary <empty|wall|wrap|mirror>
+                        what lies beyond the grid edges (default: empty)
+
+Engine:
+  --backend <dense|sparse>
+                        how rolls are stored; sparse suits huge, mostly empty maps (default: dense)
+  --counter <naive|bitboard>
+                        how the starting neighbor counts are computed (default: naive); later waves
+                        only adjust them either way, so the whole run gains far less than that pass
+  --verify              check the bitboard counter against the naive one before simulating
+
+Output:
+  --print-grids         print the grid after every wave
+  --json <path>         write per-wave statistics and removal waves as JSON
+  --csv <path>          write row,col,wave for every roll as CSV";
+
+#[cfg(feature = "animation")]
+const ANIMATION_USAGE: &str = "
+
+Animation (nothing is rendered unless a path is given):
+  --gif <path>          write the removal waves as an animated GIF
+  --apng <path>         write the removal waves as an animated PNG
+  --png-frames <dir>    write every frame as a numbered PNG into a directory
+  --asciicast <path>    write the removal waves as an asciinema recording (.cast)
+  --png <path>          write the final grid as a PNG
+  --size <pixels>       size of the box the image is fitted into (default: 1000)
+  --width <pixels>      width of that box, overriding --size
+  --height <pixels>     height of that box, overriding --size
+  --scale <pixels>      fixed pixels per cell, may be fractional (ignores the box)
+  --min-scale <pixels>  never draw cells smaller than this
+  --gridlines           draw lines between cells
+  --every <n>           only draw every nth wave, for long runs (default: 1)
+  --delay <ms>          time each wave is shown (default: 75)
+  --hold-start <ms>     time the first frame is shown (default: 2000)
+  --hold-end <ms>       time the last frame is shown (default: 2000)
+  --loops <n|infinite>  times to play the animation, at least 1 (default: infinite)
+  --palette <heat|grayscale|colorblind|file.toml|file.json>
+                        cell colors (default: heat)
+  --legend              draw a strip with every cell state's color below the grid";
+
+// continues the output section
+#[cfg(feature = "tui")]
+const TUI_USAGE: &str = "
+  --tui                 step through the waves in an interactive terminal viewer";
+
+const ANALYSIS_USAGE: &str = "
+
+Analysis (rows and columns count from 0):
+  --core                print the rolls that are never removed
+  --protect <row,col>   find rolls to add around a roll so it's never removed, searching outward from it
+                        (none of them can be left out, but a smaller set may exist)
+  --remove-first <row,col>
+                        remove a roll before the first wave and compare the cascade";
+
+fn usage() -> String {
+    [
+        USAGE,
+        #[cfg(feature = "tui")]
+        TUI_USAGE,
+        ANALYSIS_USAGE,
+        #[cfg(feature = "animation")]
+        ANIMATION_USAGE,
+    ].concat()
+}
+
+/// Command line options; anything not given keeps its default.
+pub struct Options {
+    pub rule: Rule,
+    pub backend: Backend,
+    pub counter: Counter,
+    pub verify: bool,
+    pub print_grids: bool,
+    pub json: Option<PathBuf>,
+    pub csv: Option<PathBuf>,
+    pub core: bool,
+    pub protect: Option<(usize, usize)>,
+    pub remove_first: Option<(usize, usize)>,
+    #[cfg(feature = "tui")]
+    pub tui: bool,
+    #[cfg(feature = "animation")]
+    pub animation: AnimationOptions,
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut rule = Rule::default();
+        let mut backend = Backend::Dense;
+        let mut counter = Counter::Naive;
+        let mut verify = false;
+        let mut print_grids = false;
+        let mut json = None;
+        let mut csv = None;
+        let mut core = false;
+        let mut protect = None;
+        let mut remove_first = None;
+        #[cfg(feature = "tui")]
+        let mut tui = false;
+        #[cfg(feature = "animation")]
+        let mut animation = AnimationOptions::default();
+        let mut neighborhood: Option<String> = None;
+        let mut radius: usize = 1;
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("Missing value for {}\n\n{}", flag, usage()));
+
+            match flag.as_str() {
+                "--neighborhood" => neighborhood = Some(value()?),
+                "--radius" => radius = parse_number(&flag, &value()?)?,
+                "--threshold" => rule.threshold = parse_number(&flag, &value()?)?,
+                "--compare" => rule.comparison = Comparison::parse(&value()?)?,
+                "--boundary" => rule.boundary = Boundary::parse(&value()?)?,
+                "--backend" => backend = Backend::parse(&value()?)?,
+                "--counter" => counter = Counter::parse(&value()?)?,
+                "--verify" => verify = true,
+                "--print-grids" => print_grids = true,
+                "--json" => json = Some(PathBuf::from(value()?)),
+                "--csv" => csv = Some(PathBuf::from(value()?)),
+                "--core" => core = true,
+                "--protect" => protect = Some(parse_position(&flag, &value()?)?),
+                "--remove-first" => remove_first = Some(parse_position(&flag, &value()?)?),
+                #[cfg(feature = "tui")]
+                "--tui" => tui = true,
+                #[cfg(feature = "animation")]
+                "--gif" => animation.gif = Some(PathBuf::from(value()?)),
+                #[cfg(feature = "animation")]
+                "--apng" => animation.apng = Some(PathBuf::from(value()?)),
+                #[cfg(feature = "animation")]
+                "--png-frames" => animation.png_frames = Some(PathBuf::from(value()?)),
+                #[cfg(feature = "animation")]
+                "--asciicast" => animation.asciicast = Some(PathBuf::from(value()?)),
+                #[cfg(feature = "animation")]
+                "--png" => animation.png = Some(PathBuf::from(value()?)),
+                #[cfg(feature = "animation")]
+                "--size" => animation.target_size = parse_number(&flag, &value()?)?,
+                #[cfg(feature = "animation")]
+                "--width" => animation.width = Some(parse_number(&flag, &value()?)?),
+                #[cfg(feature = "animation")]
+                "--height" => animation.height = Some(parse_number(&flag, &value()?)?),
+                #[cfg(feature = "animation")]
+                "--scale" => animation.scale = Some(parse_scale(&flag, &value()?)?),
+                #[cfg(feature = "animation")]
+                "--min-scale" => animation.min_scale = parse_scale(&flag, &value()?)?,
+                #[cfg(feature = "animation")]
+                "--gridlines" => animation.gridlines = true,
+                #[cfg(feature = "animation")]
+                "--every" => animation.every = parse_number::<NonZeroUsize>(&flag, &value()?)?.get(),
+                #[cfg(feature = "animation")]
+                "--delay" => animation.delay_ms = parse_number(&flag, &value()?)?,
+                #[cfg(feature = "animation")]
+                "--hold-start" => animation.hold_start_ms = parse_number(&flag, &value()?)?,
+                #[cfg(feature = "animation")]
+                "--hold-end" => animation.hold_end_ms = parse_number(&flag, &value()?)?,
+                #[cfg(feature = "animation")]
+                "--loops" => animation.plays = match value()?.as_str() {
+                    "infinite" => None,
+                    n => Some(parse_number::<NonZeroU16>(&flag, n)?),
+                },
+                #[cfg(feature = "animation")]
+                "--palette" => animation.palette = Palette::load(&value()?).map_err(|e| format!("Invalid palette: {}", e))?,
+                #[cfg(feature = "animation")]
+                "--legend" => animation.legend = true,
+                "-h" | "--help" => return Err(usage()),
+                other => return Err(format!("Unknown option '{}'\n\n{}", other, usage())),
+            }
+        }
+
+        rule.neighborhood = Neighborhood::parse(neighborhood.as_deref().unwrap_or("moore"), radius)?;
+
+        Ok(Self {
+            rule,
+            backend,
+            counter,
+            verify,
+            print_grids,
+            json,
+            csv,
+            core,
+            protect,
+            remove_first,
+            #[cfg(feature = "tui")]
+            tui,
+            #[cfg(feature = "animation")]
+            animation,
+        })
+    }
+}
+
+// Pixels per cell: positive, and no larger than a single cell that already fills `MAX_PIXELS`
+#[cfg(feature = "animation")]
+fn parse_scale(flag: &str, value: &str) -> Result<f64, String> {
+    let max = (animation::MAX_PIXELS as f64).sqrt();
+    let scale: f64 = parse_number(flag, value)?;
+    if scale > 0.0 && scale <= max {
+        Ok(scale)
+    } else {
+        Err(format!("Invalid value '{}' for {} (expected more than 0 and at most {})", value, flag, max))
+    }
+}
+
+// A cell given as `row,col`
+fn parse_position(flag: &str, value: &str) -> Result<(usize, usize), String> {
+    let (row, col) = value.split_once(',').ok_or_else(|| format!("Invalid value '{}' for {} (expected row,col)", value, flag))?;
+    Ok((parse_number(flag, row.trim())?, parse_number(flag, col.trim())?))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value '{}' for {}", value, flag))
+}
@@ -0,0 +1,137 @@
+#[cfg(feature = "animation")]
+use std::error::Error;
+#[cfg(feature = "animation")]
+use std::fs;
+#[cfg(feature = "animation")]
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::render::{Cell, FADE_STEPS};
+
+/// Colors used to draw each cell state, shared by the image exporters and the terminal viewer.
+///
+/// Palette files (TOML or JSON, picked by extension) list colors as `#rrggbb` strings:
+///
+/// ```toml
+/// empty = "#000000"
+/// roll = "#c8c8c8"
+/// fade = ["#fff274", "#ff9943", "#ff0000", "#220000"]
+/// ```
+///
+/// `fade` runs from just removed to almost gone and may have any number of entries; they're stretched
+/// over the fade steps.
+#[derive(Clone, Deserialize)]
+pub struct Palette {
+    empty: Color,
+    roll: Color,
+    fade: Vec<Color>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct Color([u8; 3]);
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color([r, g, b])),
+            _ => Err(format!("invalid color '{}' (expected #rrggbb)", value)),
+        }
+    }
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color([r, g, b])
+}
+
+impl Palette {
+    /// The original yellow-to-red fade.
+    pub fn heat() -> Self {
+        Self {
+            empty: rgb(0, 0, 0),
+            roll: rgb(200, 200, 200),
+            fade: vec![
+                rgb(255, 242, 116), rgb(255, 199, 92), rgb(255, 153, 67), rgb(255, 102, 41),
+                rgb(255, 0, 0), rgb(174, 0, 0), rgb(99, 0, 0), rgb(34, 0, 0),
+            ],
+        }
+    }
+
+    #[cfg(feature = "animation")]
+    pub fn grayscale() -> Self {
+        Self {
+            empty: rgb(0, 0, 0),
+            roll: rgb(128, 128, 128),
+            fade: vec![
+                rgb(255, 255, 255), rgb(224, 224, 224), rgb(192, 192, 192), rgb(160, 160, 160),
+                rgb(112, 112, 112), rgb(80, 80, 80), rgb(48, 48, 48), rgb(24, 24, 24),
+            ],
+        }
+    }
+
+    /// Viridis-style fade, which stays distinguishable under the common forms of color blindness.
+    #[cfg(feature = "animation")]
+    pub fn colorblind() -> Self {
+        Self {
+            empty: rgb(0, 0, 0),
+            roll: rgb(200, 200, 200),
+            fade: vec![
+                rgb(253, 231, 37), rgb(181, 222, 43), rgb(110, 206, 88), rgb(53, 183, 121),
+                rgb(31, 158, 137), rgb(38, 130, 142), rgb(49, 104, 142), rgb(62, 73, 137),
+            ],
+        }
+    }
+
+    /// A built-in palette by name, or else a palette file.
+    #[cfg(feature = "animation")]
+    pub fn load(name_or_path: &str) -> Result<Self, Box<dyn Error>> {
+        match name_or_path {
+            "heat" => Ok(Self::heat()),
+            "grayscale" => Ok(Self::grayscale()),
+            "colorblind" => Ok(Self::colorblind()),
+            path => Self::from_file(Path::new(path)),
+        }
+    }
+
+    #[cfg(feature = "animation")]
+    fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let palette: Palette = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&text)?,
+            Some("json") => serde_json::from_str(&text)?,
+            _ => return Err(format!("{}: palette files must end in .toml or .json", path.display()).into()),
+        };
+
+        if palette.fade.is_empty() {
+            return Err(format!("{}: fade needs at least one color", path.display()).into());
+        }
+        Ok(palette)
+    }
+
+    /// The `[r, g, b]` color of a cell.
+    pub fn color(&self, cell: Cell) -> [u8; 3] {
+        let Color(rgb) = match cell {
+            Cell::Empty => self.empty,
+            Cell::Roll => self.roll,
+            Cell::Fading(n) => self.fade[n * self.fade.len() / FADE_STEPS],
+        };
+        rgb
+    }
+
+    /// Every cell state in legend order: roll, fading from newest to oldest, empty.
+    #[cfg(feature = "animation")]
+    pub fn legend(&self) -> Vec<[u8; 3]> {
+        std::iter::once(Cell::Roll)
+            .chain((0..FADE_STEPS).map(Cell::Fading))
+            .chain(std::iter::once(Cell::Empty))
+            .map(|cell| self.color(cell))
+            .collect()
+    }
+}
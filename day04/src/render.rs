@@ -0,0 +1,49 @@
+use std::fmt;
+
+use common::Grid;
+
+use crate::matrix::Simulation;
+
+/// Number of frames a removed roll takes to fade out to an empty cell.
+pub const FADE_STEPS: usize = 8;
+
+// Text symbols for each fade step, brightest (just removed) first
+const FADE_SYMBOLS: [char; FADE_STEPS] = ['X', 'x', 'Y', 'y', 'Z', 'z', 'A', 'a'];
+
+/// What a single cell looks like in a rendered frame.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Roll,
+    /// A removed roll, `n` frames after its removal (0 = removed this wave).
+    Fading(usize),
+}
+
+impl Cell {
+    pub fn symbol(self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Roll => '@',
+            Cell::Fading(n) => FADE_SYMBOLS[n],
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// Derive the display state after `wave` waves purely from the initial rolls and their removal waves.
+pub fn frame(sim: &Simulation, wave: usize) -> Grid<Cell> {
+    let mut cells = Grid::new(sim.width, sim.height, Cell::Empty);
+    for (&pos, &removed) in sim.positions.iter().zip(&sim.removed_at) {
+        cells[pos] = match removed {
+            Some(r) if r <= wave && wave - r < FADE_STEPS => Cell::Fading(wave - r),
+            Some(r) if r <= wave => Cell::Empty,
+            _ => Cell::Roll,
+        };
+    }
+    cells
+}
@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::matrix::Simulation;
+
+/// Per-wave statistics and removal order of a simulation, in a shape that exports cleanly to JSON.
+#[derive(Serialize)]
+pub struct Report<'a> {
+    pub width: usize,
+    pub height: usize,
+    pub initial_rolls: u64,
+    pub total_removed: u64,
+    pub waves: Vec<WaveStats>,
+    /// (row, col) of every roll left standing.
+    pub survivors: Vec<(usize, usize)>,
+    #[serde(skip)]
+    sim: &'a Simulation,
+}
+
+// The JSON file adds the removal wave of every roll, which is only built when saving since it's as long as the
+// list of rolls
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    report: &'a Report<'a>,
+    /// `[row, col, wave]` for every initial roll in row-major order, like the CSV, with a `null` wave for survivors.
+    removed_at: Vec<(usize, usize, Option<usize>)>,
+}
+
+#[derive(Serialize)]
+pub struct WaveStats {
+    pub wave: usize,
+    pub removed: u64,
+    pub remaining: u64,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(sim: &'a Simulation) -> Self {
+        let survivors: Vec<(usize, usize)> = sim.survivors().collect();
+        let total_removed = sim.total_removed();
+        let initial_rolls = sim.initial_rolls();
+
+        let mut remaining = initial_rolls;
+        let waves = sim.removals.iter().enumerate()
+            .map(|(i, &removed)| {
+                remaining -= removed;
+                WaveStats {wave: i + 1, removed, remaining}
+            })
+            .collect();
+
+        Self {
+            width: sim.width,
+            height: sim.height,
+            initial_rolls,
+            total_removed,
+            waves,
+            survivors,
+            sim,
+        }
+    }
+
+    pub fn print_summary(&self) {
+        for stats in &self.waves {
+            println!("Wave {}: removed {}, remaining {}", stats.wave, stats.removed, stats.remaining);
+        }
+        println!("Surviving core: {} of {} rolls", self.survivors.len(), self.initial_rolls);
+    }
+
+    pub fn save_json(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let removed_at = self.sim.positions.iter().zip(&self.sim.removed_at)
+            .map(|(&(row, col), &removed)| (row, col, removed))
+            .collect();
+        serde_json::to_writer_pretty(&mut out, &JsonReport {report: self, removed_at})?;
+        writeln!(out)?;
+        out.flush()
+    }
+
+    /// One line per initial roll: `row,col,wave`, with an empty wave for survivors.
+    pub fn save_csv(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "row,col,wave")?;
+
+        for (&(row, col), removed) in self.sim.positions.iter().zip(&self.sim.removed_at) {
+            match removed {
+                Some(wave) => writeln!(out, "{},{},{}", row, col, wave)?,
+                None => writeln!(out, "{},{},", row, col)?,
+            }
+        }
+        out.flush()
+    }
+}
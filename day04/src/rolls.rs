@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use common::Grid;
+
+/// Where the rolls are. The removal simulation keeps its own per-roll state, so all it needs from a map is
+/// the list of rolls and which roll (if any) sits at a cell.
+pub trait RollMap {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    /// Every roll in row-major order; a roll's index in this list is its number.
+    fn positions(&self) -> &[(usize, usize)];
+    /// Number of the roll at `pos`, if there is one.
+    fn roll_at(&self, pos: (usize, usize)) -> Option<usize>;
+}
+
+/// How a `RollMap` is stored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    /// A roll number for every cell (4 bytes per cell), fastest for typical, fairly full maps.
+    Dense,
+    /// A hash map holding only the rolls, for huge maps that are mostly empty.
+    Sparse,
+}
+
+impl Backend {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "dense" => Ok(Backend::Dense),
+            "sparse" => Ok(Backend::Sparse),
+            other => Err(format!("Unknown backend '{}' (expected dense or sparse)", other)),
+        }
+    }
+
+    /// Build a map of a `width` x `height` area from its rolls, which must be in row-major order.
+    pub fn build(self, width: usize, height: usize, positions: Vec<(usize, usize)>) -> Box<dyn RollMap> {
+        match self {
+            Backend::Dense => Box::new(DenseRolls::new(width, height, positions)),
+            Backend::Sparse => Box::new(SparseRolls::new(width, height, positions)),
+        }
+    }
+}
+
+// Marks cells without a roll in `DenseRolls`
+const NO_ROLL: u32 = u32::MAX;
+
+pub struct DenseRolls {
+    ids: Grid<u32>,
+    positions: Vec<(usize, usize)>,
+}
+
+impl DenseRolls {
+    pub fn new(width: usize, height: usize, positions: Vec<(usize, usize)>) -> Self {
+        assert!(positions.len() < NO_ROLL as usize, "too many rolls for the dense backend");
+
+        let mut ids = Grid::new(width, height, NO_ROLL);
+        for (id, &pos) in positions.iter().enumerate() {
+            ids[pos] = id as u32;
+        }
+        Self {ids, positions}
+    }
+}
+
+impl RollMap for DenseRolls {
+    fn width(&self) -> usize {
+        self.ids.width()
+    }
+
+    fn height(&self) -> usize {
+        self.ids.height()
+    }
+
+    fn positions(&self) -> &[(usize, usize)] {
+        &self.positions
+    }
+
+    fn roll_at(&self, (row, col): (usize, usize)) -> Option<usize> {
+        self.ids.get(row, col).filter(|&&id| id != NO_ROLL).map(|&id| id as usize)
+    }
+}
+
+// Keys are plain cell indices, which a multiply-rotate mixes well enough and much faster than the default SipHash
+#[derive(Default)]
+struct CellHasher(u64);
+
+impl Hasher for CellHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(u64::from(b));
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub struct SparseRolls {
+    width: usize,
+    height: usize,
+    // roll number by flat cell index
+    ids: HashMap<u64, usize, BuildHasherDefault<CellHasher>>,
+    positions: Vec<(usize, usize)>,
+}
+
+impl SparseRolls {
+    pub fn new(width: usize, height: usize, positions: Vec<(usize, usize)>) -> Self {
+        let ids = positions.iter().enumerate().map(|(id, &pos)| (cell_key(width, pos), id)).collect();
+        Self {width, height, ids, positions}
+    }
+}
+
+impl RollMap for SparseRolls {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn positions(&self) -> &[(usize, usize)] {
+        &self.positions
+    }
+
+    fn roll_at(&self, pos: (usize, usize)) -> Option<usize> {
+        if pos.0 >= self.height || pos.1 >= self.width {
+            return None;
+        }
+        self.ids.get(&cell_key(self.width, pos)).copied()
+    }
+}
+
+fn cell_key(width: usize, (row, col): (usize, usize)) -> u64 {
+    row as u64 * width as u64 + col as u64
+}
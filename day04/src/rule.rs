@@ -0,0 +1,179 @@
+use std::fmt;
+
+/// Which surrounding cells count as neighbors.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Neighborhood {
+    /// Cells within the given Manhattan distance (radius 1 = the 4 orthogonal cells).
+    VonNeumann(usize),
+    /// Cells within the given Chebyshev distance (radius 1 = the 8 surrounding cells).
+    Moore(usize),
+    /// Arbitrary (row, col) offsets; they don't need to be symmetric.
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Neighborhood {
+    /// Offsets (row, col) of every neighbor, excluding the cell itself and without duplicates.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        let square = |r: usize| {
+            let r = r as isize;
+            (-r..=r).flat_map(move |dr| (-r..=r).map(move |dc| (dr, dc)))
+        };
+
+        let mut offsets: Vec<(isize, isize)> = match self {
+            Neighborhood::VonNeumann(r) => square(*r).filter(|(dr, dc)| dr.unsigned_abs() + dc.unsigned_abs() <= *r).collect(),
+            Neighborhood::Moore(r) => square(*r).collect(),
+            Neighborhood::Custom(offsets) => offsets.clone(),
+        };
+
+        offsets.retain(|&o| o != (0, 0));
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+
+    /// Parse `moore`, `von-neumann` (both with the given radius) or `custom:dr,dc;dr,dc;...`.
+    pub fn parse(value: &str, radius: usize) -> Result<Self, String> {
+        match value {
+            "moore" => Ok(Neighborhood::Moore(radius)),
+            "von-neumann" => Ok(Neighborhood::VonNeumann(radius)),
+            _ => {
+                let list = value.strip_prefix("custom:")
+                    .ok_or_else(|| format!("Unknown neighborhood '{}' (expected moore, von-neumann or custom:dr,dc;...)", value))?;
+
+                list.split(';')
+                    .map(|pair| {
+                        let (dr, dc) = pair.split_once(',').ok_or_else(|| format!("Malformed offset '{}' (expected dr,dc)", pair))?;
+                        let parse = |v: &str| v.trim().parse::<isize>().map_err(|e| format!("Malformed offset '{}': {}", pair, e));
+                        Ok((parse(dr)?, parse(dc)?))
+                    })
+                    .collect::<Result<Vec<_>, String>>()
+                    .map(Neighborhood::Custom)
+            }
+        }
+    }
+}
+
+/// How a roll's neighbor count is compared against the threshold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessEq,
+    Equal,
+    GreaterEq,
+    Greater,
+}
+
+impl Comparison {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "lt" => Ok(Comparison::Less),
+            "le" => Ok(Comparison::LessEq),
+            "eq" => Ok(Comparison::Equal),
+            "ge" => Ok(Comparison::GreaterEq),
+            "gt" => Ok(Comparison::Greater),
+            other => Err(format!("Unknown comparison '{}' (expected lt, le, eq, ge or gt)", other)),
+        }
+    }
+
+    fn holds(self, count: usize, threshold: usize) -> bool {
+        match self {
+            Comparison::Less => count < threshold,
+            Comparison::LessEq => count <= threshold,
+            Comparison::Equal => count == threshold,
+            Comparison::GreaterEq => count >= threshold,
+            Comparison::Greater => count > threshold,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Less => "<",
+            Comparison::LessEq => "<=",
+            Comparison::Equal => "==",
+            Comparison::GreaterEq => ">=",
+            Comparison::Greater => ">",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// What lies beyond the edges of the grid when counting neighbors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// Outside cells are empty (the puzzle's behavior).
+    Empty,
+    /// Outside cells hold a roll that can never be removed.
+    Wall,
+    /// The grid wraps around into a torus.
+    Wrap,
+    /// The grid is reflected at its edges, without repeating the edge cell.
+    Mirror,
+}
+
+/// Where a neighbor offset lands once the boundary has been applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Landing {
+    Cell(usize, usize),
+    Wall,
+    Outside,
+}
+
+impl Boundary {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "empty" => Ok(Boundary::Empty),
+            "wall" => Ok(Boundary::Wall),
+            "wrap" => Ok(Boundary::Wrap),
+            "mirror" => Ok(Boundary::Mirror),
+            other => Err(format!("Unknown boundary '{}' (expected empty, wall, wrap or mirror)", other)),
+        }
+    }
+
+    /// Resolve (row + dr, col + dc) on a `height` x `width` grid.
+    pub fn land(self, (row, col): (usize, usize), (dr, dc): (isize, isize), height: usize, width: usize) -> Landing {
+        let r = row as isize + dr;
+        let c = col as isize + dc;
+
+        match self {
+            _ if (0..height as isize).contains(&r) && (0..width as isize).contains(&c) => Landing::Cell(r as usize, c as usize),
+            Boundary::Empty => Landing::Outside,
+            Boundary::Wall => Landing::Wall,
+            Boundary::Wrap => Landing::Cell(r.rem_euclid(height as isize) as usize, c.rem_euclid(width as isize) as usize),
+            Boundary::Mirror => Landing::Cell(reflect(r, height), reflect(c, width)),
+        }
+    }
+}
+
+// Reflect an index into 0..len, bouncing back and forth off both ends: -1 -> 1, len -> len - 2, ...
+fn reflect(index: isize, len: usize) -> usize {
+    if len <= 1 {
+        return 0;
+    }
+    let period = 2 * (len as isize - 1);
+    let m = index.rem_euclid(period);
+    (if m < len as isize { m } else { period - m }) as usize
+}
+
+/// When a roll is accessible (and therefore removable): `neighbor count <comparison> threshold`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub neighborhood: Neighborhood,
+    pub threshold: usize,
+    pub comparison: Comparison,
+    pub boundary: Boundary,
+}
+
+impl Default for Rule {
+    // the puzzle's rule: fewer than 4 of the 8 surrounding cells hold a roll
+    fn default() -> Self {
+        Self {neighborhood: Neighborhood::Moore(1), threshold: 4, comparison: Comparison::Less, boundary: Boundary::Empty}
+    }
+}
+
+impl Rule {
+    pub fn is_accessible(&self, neighbor_count: usize) -> bool {
+        self.comparison.holds(neighbor_count, self.threshold)
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, terminal};
+use common::Grid;
+
+use crate::matrix::Simulation;
+use crate::palette::Palette;
+use crate::render::{self, Cell, FADE_STEPS};
+
+const HELP: &str = "space play/pause  \u{2190}/\u{2192} step  home/end first/last  +/- speed  q quit";
+
+// Rows below the grid for the wave counts and the key help
+const STATUS_LINES: u16 = 2;
+
+// Puts the terminal back the way it was however the viewer exits, including on errors and panics
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// How grid cells map onto screen pixels, each terminal row holding two pixels stacked in a half block.
+#[derive(Clone, Copy)]
+enum Zoom {
+    /// Every cell is drawn as an n x n square of pixels.
+    In(usize),
+    /// Every pixel stands for an n x n block of cells.
+    Out(usize),
+}
+
+impl Zoom {
+    // Largest zoom that fits a `width` x `height` grid into `cols` x `pixel_rows`
+    fn fit(width: usize, height: usize, cols: usize, pixel_rows: usize) -> Self {
+        if width == 0 || height == 0 {
+            return Zoom::In(1);
+        }
+        if width <= cols && height <= pixel_rows {
+            Zoom::In((cols / width).min(pixel_rows / height))
+        } else {
+            Zoom::Out(width.div_ceil(cols.max(1)).max(height.div_ceil(pixel_rows.max(1))))
+        }
+    }
+
+    // Screen pixels needed for `cells` cells
+    fn pixels(self, cells: usize) -> usize {
+        match self {
+            Zoom::In(n) => cells * n,
+            Zoom::Out(n) => cells.div_ceil(n),
+        }
+    }
+
+    // Cells shown by one pixel along an axis of `len` cells
+    fn cells(self, pixel: usize, len: usize) -> Range<usize> {
+        match self {
+            Zoom::In(n) => pixel / n..pixel / n + 1,
+            Zoom::Out(n) => pixel * n..((pixel + 1) * n).min(len),
+        }
+    }
+}
+
+// When zoomed out, a pixel shows the most eye-catching cell of its block: the most recently removed roll,
+// else any standing roll
+fn prominence(cell: Cell) -> usize {
+    match cell {
+        Cell::Fading(n) => n,
+        Cell::Roll => FADE_STEPS,
+        Cell::Empty => FADE_STEPS + 1,
+    }
+}
+
+struct Viewer<'a> {
+    sim: &'a Simulation,
+    palette: &'a Palette,
+    wave: usize,
+    last_wave: usize,
+    playing: bool,
+    delay: Duration,
+}
+
+impl Viewer<'_> {
+    fn color(&self, cell: Cell) -> Color {
+        let [r, g, b] = self.palette.color(cell);
+        Color::Rgb {r, g, b}
+    }
+
+    fn pixel(&self, frame: &Grid<Cell>, zoom: Zoom, x: usize, y: usize) -> Option<Cell> {
+        let rows = zoom.cells(y, frame.height());
+        let cols = zoom.cells(x, frame.width());
+        rows.flat_map(|r| cols.clone().filter_map(move |c| frame.get(r, c)))
+            .copied()
+            .min_by_key(|&cell| prominence(cell))
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let grid_rows = rows.saturating_sub(STATUS_LINES) as usize;
+
+        let frame = render::frame(self.sim, self.wave);
+        let zoom = Zoom::fit(frame.width(), frame.height(), cols as usize, grid_rows * 2);
+        let width = zoom.pixels(frame.width()).min(cols as usize);
+        let left = (cols as usize - width) / 2;
+
+        // the whole screen is built up front and written at once, so it doesn't flicker
+        let mut screen = String::new();
+        for row in 0..grid_rows {
+            write!(screen, "{}{}", cursor::MoveTo(0, row as u16), terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
+            if 2 * row >= zoom.pixels(frame.height()) {
+                continue;
+            }
+            write!(screen, "{}", cursor::MoveTo(left as u16, row as u16)).unwrap();
+
+            // the upper pixel is the half block's foreground, the lower one its background
+            let mut current = None;
+            for x in 0..width {
+                let top = self.pixel(&frame, zoom, x, 2 * row).map_or(Color::Reset, |cell| self.color(cell));
+                let bottom = self.pixel(&frame, zoom, x, 2 * row + 1).map_or(Color::Reset, |cell| self.color(cell));
+                if current != Some((top, bottom)) {
+                    write!(screen, "{}{}", SetForegroundColor(top), SetBackgroundColor(bottom)).unwrap();
+                    current = Some((top, bottom));
+                }
+                screen.push('\u{2580}');
+            }
+            write!(screen, "{}", ResetColor).unwrap();
+        }
+
+        let waves = self.sim.removals.len();
+        let removed: u64 = self.sim.removals[..self.wave.min(waves)].iter().sum();
+        let this_wave = match self.wave {
+            w if (1..=waves).contains(&w) => self.sim.removals[w - 1],
+            _ => 0,
+        };
+        let zoom_text = match zoom {
+            Zoom::In(n) => format!("{}:1", n),
+            Zoom::Out(n) => format!("1:{}", n),
+        };
+        let status = format!(
+            "Wave {}/{}  removed {} ({} total)  remaining {}  zoom {}  {}",
+            self.wave, self.last_wave, this_wave, removed, self.sim.initial_rolls() - removed, zoom_text,
+            if self.playing { "playing" } else { "paused" },
+        );
+
+        for (line, text) in [status.as_str(), HELP].into_iter().enumerate() {
+            let row = rows.saturating_sub(STATUS_LINES) + line as u16;
+            let text: String = text.chars().take(cols as usize).collect();
+            write!(screen, "{}{}{}", cursor::MoveTo(0, row), terminal::Clear(terminal::ClearType::CurrentLine), text).unwrap();
+        }
+
+        out.write_all(screen.as_bytes())?;
+        out.flush()
+    }
+
+    // Handle one key press; returns false to quit
+    fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char(' ') => {
+                // playing from the end starts over
+                if !self.playing && self.wave == self.last_wave {
+                    self.wave = 0;
+                }
+                self.playing = !self.playing;
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.playing = false;
+                self.wave = (self.wave + 1).min(self.last_wave);
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.playing = false;
+                self.wave = self.wave.saturating_sub(1);
+            }
+            KeyCode::Home => self.wave = 0,
+            KeyCode::End => self.wave = self.last_wave,
+            KeyCode::Char('+') | KeyCode::Char('=') => self.delay = (self.delay / 2).max(Duration::from_millis(10)),
+            KeyCode::Char('-') => self.delay = (self.delay * 2).min(Duration::from_secs(2)),
+            _ => {}
+        }
+        true
+    }
+}
+
+/// Interactive viewer of a finished simulation: plays the waves like the GIF, in the same colors, with pause,
+/// stepping back and forth, and the grid zoomed to fit the terminal.
+pub fn run(sim: &Simulation, palette: &Palette) -> io::Result<()> {
+    let mut viewer = Viewer {
+        sim,
+        palette,
+        wave: 0,
+        last_wave: sim.faded_wave(),
+        playing: true,
+        delay: Duration::from_millis(150),
+    };
+
+    let _screen = RawScreen::enter()?;
+    let mut out = io::stdout();
+
+    loop {
+        viewer.draw(&mut out)?;
+
+        // while paused, just wait for input
+        let timeout = if viewer.playing { viewer.delay } else { Duration::from_secs(3600) };
+        if event::poll(timeout)? {
+            // anything else, like a resize, just redraws
+            if let Event::Key(key) = event::read()? && key.kind == KeyEventKind::Press && !viewer.key(key.code, key.modifiers) {
+                break;
+            }
+        } else if viewer.playing {
+            if viewer.wave < viewer.last_wave {
+                viewer.wave += 1;
+            } else {
+                viewer.playing = false;
+            }
+        }
+    }
+    Ok(())
+}
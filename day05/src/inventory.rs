@@ -0,0 +1,68 @@
+/// A fresh ingredient range as written in the input, bounds inclusive.
+#[derive(Clone, Copy, Debug)]
+pub struct FreshRange {
+    /// Line of the input it came from, 1-based.
+    pub line: usize,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The fresh ranges, then (after a blank line) the available ingredient ids.
+#[derive(Debug, Default)]
+pub struct Inventory {
+    pub ranges: Vec<FreshRange>,
+    pub ids: Vec<u64>,
+}
+
+impl Inventory {
+    /// Parse the input lines, rejecting anything that isn't a range (`start-end` or a single id) before the
+    /// first blank line or an id after it. Whitespace around values is ignored, as are blank lines among the
+    /// ids. Inverted ranges like `20-10` are an error unless `swap_inverted` is set.
+    pub fn parse<S: AsRef<str>>(lines: &[S], swap_inverted: bool) -> Result<Self, String> {
+        let mut inventory = Inventory::default();
+        let mut in_ranges = true;
+
+        for (idx, line) in lines.iter().enumerate() {
+            let text = line.as_ref().trim();
+            let error = |e: String| format!("Line {}: {}", idx + 1, e);
+
+            if text.is_empty() {
+                in_ranges = false;
+            } else if in_ranges {
+                let (start, end) = parse_range(text, swap_inverted).map_err(error)?;
+                inventory.ranges.push(FreshRange {line: idx + 1, start, end});
+            } else {
+                inventory.ids.push(parse_id(text, "id").map_err(error)?);
+            }
+        }
+
+        Ok(inventory)
+    }
+}
+
+// `start-end` or a lone id standing for a range of one
+fn parse_range(text: &str, swap_inverted: bool) -> Result<(u64, u64), String> {
+    let (start, end) = match text.split_once('-') {
+        Some((start, end)) => (parse_id(start.trim(), "range start")?, parse_id(end.trim(), "range end")?),
+        None => {
+            let id = parse_id(text, "range")?;
+            (id, id)
+        }
+    };
+
+    if start <= end {
+        Ok((start, end))
+    } else if swap_inverted {
+        Ok((end, start))
+    } else {
+        Err(format!("inverted range {}-{} (use --swap-inverted to accept it as {}-{})", start, end, end, start))
+    }
+}
+
+fn parse_id(text: &str, what: &str) -> Result<u64, String> {
+    if text.is_empty() {
+        Err(format!("missing {}", what))
+    } else {
+        text.parse().map_err(|e| format!("invalid {} '{}': {}", what, text, e))
+    }
+}
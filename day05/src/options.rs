@@ -0,0 +1,49 @@
+use crate::query::Format;
+
+const USAGE: &str = "\
+Usage: day05 [options]
+
+Input:
+  --swap-inverted       accept ranges written high-low (like 20-10) by swapping their bounds,
+                        instead of rejecting them
+
+Queries (replace the part 1 and 2 counts):
+  --query               list every id as fresh or spoiled, with the lines of the ranges containing it
+  --spoiled-only        only list spoiled ids (implies --query)
+  --format <text|json>  how the ids are listed (default: text)";
+
+/// Command line options; anything not given keeps its default.
+pub struct Options {
+    pub swap_inverted: bool,
+    pub query: bool,
+    pub spoiled_only: bool,
+    pub format: Format,
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut swap_inverted = false;
+        let mut query = false;
+        let mut spoiled_only = false;
+        let mut format = Format::Text;
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("Missing value for {}\n\n{}", flag, USAGE));
+
+            match flag.as_str() {
+                "--swap-inverted" => swap_inverted = true,
+                "--query" => query = true,
+                "--spoiled-only" => {
+                    query = true;
+                    spoiled_only = true;
+                }
+                "--format" => format = Format::parse(&value()?)?,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other => return Err(format!("Unknown option '{}'\n\n{}", other, USAGE)),
+            }
+        }
+
+        Ok(Self {swap_inverted, query, spoiled_only, format})
+    }
+}
@@ -0,0 +1,91 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::inventory::Inventory;
+
+/// How query results are printed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            other => Err(format!("Unknown format '{}' (expected text or json)", other)),
+        }
+    }
+}
+
+/// Whether an ingredient id is fresh, and the input ranges that say so.
+#[derive(Debug, Serialize)]
+pub struct IdMatch {
+    pub id: u64,
+    pub fresh: bool,
+    /// Line numbers of every range containing the id, ascending; empty for spoiled ids.
+    pub range_lines: Vec<usize>,
+}
+
+/// Match every id against the original (unmerged) ranges, in input order.
+///
+/// The ids are visited in ascending order while sweeping over the ranges sorted by start: ranges join the
+/// active set once they start at or before the id and leave it once they end before it, so whatever is
+/// active contains the id. That's O((n + m) log n) plus the size of the output, however much ranges overlap.
+pub fn matches(inventory: &Inventory) -> Vec<IdMatch> {
+    let ids = &inventory.ids;
+    let mut ranges: Vec<_> = inventory.ranges.iter().collect();
+    ranges.sort_by_key(|range| range.start);
+    let mut order: Vec<usize> = (0..ids.len()).collect();
+    order.sort_by_key(|&i| ids[i]);
+
+    let mut lines = vec![Vec::new(); ids.len()];
+    // (end, line) of the active ranges, soonest ending on top
+    let mut active = BinaryHeap::new();
+    let mut next = 0;
+
+    for i in order {
+        let id = ids[i];
+        while let Some(range) = ranges.get(next) && range.start <= id {
+            active.push(Reverse((range.end, range.line)));
+            next += 1;
+        }
+        while active.peek().is_some_and(|&Reverse((end, _))| end < id) {
+            active.pop();
+        }
+
+        lines[i] = active.iter().map(|&Reverse((_, line))| line).collect();
+        lines[i].sort_unstable();
+    }
+
+    ids.iter().zip(lines)
+        .map(|(&id, range_lines)| IdMatch {id, fresh: !range_lines.is_empty(), range_lines})
+        .collect()
+}
+
+/// Print one line per id, or all of them as a JSON array.
+pub fn write(out: &mut impl Write, matches: &[IdMatch], format: Format) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, matches)?;
+            writeln!(out)
+        }
+        Format::Text => {
+            for m in matches {
+                if m.fresh {
+                    let lines: Vec<String> = m.range_lines.iter().map(|line| line.to_string()).collect();
+                    let label = if lines.len() == 1 { "range on line" } else { "ranges on lines" };
+                    writeln!(out, "{} fresh ({} {})", m.id, label, lines.join(", "))?;
+                } else {
+                    writeln!(out, "{} spoiled", m.id)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}